@@ -1,6 +1,9 @@
 use super::OID;
-use std::cast;
+use std::{cast, ptr};
+use std::libc::{c_int, size_t};
 use std::vec::raw::buf_as_slice;
+use super::{git_error, last_error, GitError};
+use super::filter::from_c_buf_bytes;
 use super::Repository;
 use ext;
 
@@ -9,6 +12,12 @@ pub struct Blob<'r> {
     priv owner: &'r Repository,
 }
 
+// the raw pointer is needed by the filter module, but is not part of the public API
+#[doc(hidden)]
+pub fn raw_blob(blob: &Blob) -> *ext::git_blob {
+    blob.blob
+}
+
 impl<'r> Blob<'r> {
     // TODO: make this function priv
     pub fn new(blob: *ext::git_blob, owner: &'r Repository) -> Blob<'r>
@@ -38,9 +47,6 @@ impl<'r> Blob<'r> {
         unsafe {
             let ptr:*u8 = cast::transmute(ext::git_blob_rawcontent(self.blob));
             let size = ext::git_blob_rawsize(self.blob);
-            if(size < 0) {
-                fail!(~"negative blob size")
-            }
             buf_as_slice(ptr, size as uint, f)
         }
     }
@@ -56,6 +62,30 @@ impl<'r> Blob<'r> {
             ext::git_blob_is_binary(self.blob) != 0
         }
     }
+
+    /// Get the content of the blob as it would be checked out at `as_path`,
+    /// with the filters configured for that path applied (e.g. CRLF conversion).
+    ///
+    /// Binary content is returned unfiltered.
+    pub fn filtered_content(&self, as_path: &str) -> Result<~[u8], (~str, GitError)>
+    {
+        let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
+        let opts = ext::git_blob_filter_options {
+            version: ext::GIT_BLOB_FILTER_OPTIONS_VERSION,
+            flags: ext::GIT_BLOB_FILTER_CHECK_FOR_BINARY,
+            commit_id: ptr::null(),
+            attr_commit_id: OID { id: [0, ..20] },
+        };
+        as_path.with_c_str(|c_path| {
+            unsafe {
+                if ext::git_blob_filter(&mut buf, self.blob, c_path, &opts) == 0 {
+                    Ok( from_c_buf_bytes(&mut buf) )
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
 }
 
 #[unsafe_destructor]
@@ -66,3 +96,73 @@ impl<'r> Drop for Blob<'r> {
         }
     }
 }
+
+/// A stream writing a new blob to the repository, see `Repository::blob_writer`
+///
+/// Nothing is stored until `commit` is called. Write errors raise git_error.
+pub struct BlobWriter<'r> {
+    priv stream: *ext::git_writestream,
+    priv owner: &'r Repository,
+}
+
+impl<'r> BlobWriter<'r> {
+    // TODO: make this function priv
+    pub fn new(stream: *ext::git_writestream, owner: &'r Repository) -> BlobWriter<'r>
+    {
+        BlobWriter {
+            stream: stream,
+            owner: owner
+        }
+    }
+
+    /// Write `buf` to the stream, returning the error instead of raising it
+    pub fn write_chunk(&mut self, buf: &[u8]) -> Result<(), (~str, GitError)>
+    {
+        unsafe {
+            let write: extern "C" fn(*ext::git_writestream, *u8, size_t) -> c_int =
+                cast::transmute((*self.stream).write);
+            if write(self.stream, buf.as_ptr(), buf.len() as size_t) == 0 {
+                Ok(())
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Finish writing, and store the blob in the repository.
+    /// Returns the id of the blob.
+    pub fn commit(self) -> Result<OID, (~str, GitError)>
+    {
+        let mut oid = OID { id: [0, ..20] };
+        let stream = self.stream;
+        unsafe {
+            // the stream is freed by libgit2 whatever the outcome
+            cast::forget(self);
+            if ext::git_blob_create_from_stream_commit(&mut oid, stream) == 0 {
+                Ok(oid)
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+}
+
+impl<'r> Writer for BlobWriter<'r> {
+    fn write(&mut self, buf: &[u8])
+    {
+        match self.write_chunk(buf) {
+            Ok(()) => (),
+            Err(e) => git_error::cond.raise(e),
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<'r> Drop for BlobWriter<'r> {
+    fn drop(&mut self) {
+        unsafe {
+            let free: extern "C" fn(*ext::git_writestream) = cast::transmute((*self.stream).free);
+            free(self.stream);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::ptr;
+use std::libc::{c_int, c_uint};
+use ext;
+use super::{GitError, last_error, with_c_strarray};
+use super::Repository;
+use super::tree::Tree;
+
+pub enum CheckoutStrategy {
+    /** Default is a dry run, no actual updates */
+    GIT_CHECKOUT_NONE = 0,
+    /** Allow safe updates that cannot overwrite uncommitted data */
+    GIT_CHECKOUT_SAFE = (1 << 0),
+    /** Allow all updates to force working directory to look like index */
+    GIT_CHECKOUT_FORCE = (1 << 1),
+    /** Allow checkout to recreate missing files */
+    GIT_CHECKOUT_RECREATE_MISSING = (1 << 2),
+    /** Allow checkout to make safe updates even if conflicts are found */
+    GIT_CHECKOUT_ALLOW_CONFLICTS = (1 << 4),
+    /** Remove untracked files not in index (that are not ignored) */
+    GIT_CHECKOUT_REMOVE_UNTRACKED = (1 << 5),
+    /** Remove ignored files not in index */
+    GIT_CHECKOUT_REMOVE_IGNORED = (1 << 6),
+    /** Only update existing files, don't create new ones */
+    GIT_CHECKOUT_UPDATE_ONLY = (1 << 7),
+    /** Normally checkout updates index entries as it goes; this stops that */
+    GIT_CHECKOUT_DONT_UPDATE_INDEX = (1 << 8),
+    /** Don't refresh index/config/etc before doing checkout */
+    GIT_CHECKOUT_NO_REFRESH = (1 << 9),
+    /** Treat pathspec as simple list of exact match file paths */
+    GIT_CHECKOUT_DISABLE_PATHSPEC_MATCH = (1 << 13),
+    /** Ignore directories in use, they will be left empty */
+    GIT_CHECKOUT_SKIP_LOCKED_DIRECTORIES = (1 << 18),
+}
+
+pub struct CheckoutOptions {
+    strategy: ~[CheckoutStrategy],
+    /// Don't apply filters like CRLF conversion
+    disable_filters: bool,
+    /// Mode for new directories, 0 for the default 0755
+    dir_mode: uint,
+    /// Mode for new files, 0 for the default 0644 or 0755 as dictated by the blob
+    file_mode: uint,
+    /// Only check out paths matching these pathspecs. Empty means all paths
+    paths: ~[~str],
+}
+
+impl CheckoutOptions {
+    pub fn new() -> CheckoutOptions {
+        CheckoutOptions {
+            strategy: ~[GIT_CHECKOUT_SAFE],
+            disable_filters: false,
+            dir_mode: 0,
+            file_mode: 0,
+            paths: ~[],
+        }
+    }
+}
+
+pub fn with_c_checkout_opts<T>(opts: &CheckoutOptions, f: |&ext::git_checkout_options| -> T) -> T
+{
+    let paths: ~[&str] = opts.paths.iter().map(|p| p.as_slice()).collect();
+    with_c_strarray(paths, |c_paths| {
+        let c_opts = ext::git_checkout_options {
+            version: ext::GIT_CHECKOUT_OPTIONS_VERSION,
+            checkout_strategy: opts.strategy.iter().fold(0, |acc, &s| acc | (s as c_uint)),
+            disable_filters: opts.disable_filters as c_int,
+            dir_mode: opts.dir_mode as c_uint,
+            file_mode: opts.file_mode as c_uint,
+            file_open_flags: 0,
+            notify_flags: 0,
+            notify_cb: ptr::null(),
+            notify_payload: ptr::null(),
+            progress_cb: ptr::null(),
+            progress_payload: ptr::null(),
+            paths: *c_paths,
+            baseline: ptr::null(),
+            baseline_index: ptr::null(),
+            target_directory: ptr::null(),
+            ancestor_label: ptr::null(),
+            our_label: ptr::null(),
+            their_label: ptr::null(),
+            perfdata_cb: ptr::null(),
+            perfdata_payload: ptr::null(),
+        };
+        f(&c_opts)
+    })
+}
+
+impl Repository {
+    /// Updates files in the index and working tree to match the content of the given tree.
+    ///
+    /// HEAD is not updated; point it at the corresponding commit afterwards
+    /// to complete a branch switch or a fast-forward.
+    pub fn checkout_tree(&self, tree: &Tree, opts: &CheckoutOptions)
+        -> Result<(), (~str, GitError)>
+    {
+        with_c_checkout_opts(opts, |c_opts| {
+            unsafe {
+                if ext::git_checkout_tree(self.repo, tree.tree, c_opts) == 0 {
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,636 @@
+use std::{ptr, str, cast};
+use std::libc::{c_char, c_int, c_void};
+use ext;
+use super::{GitError, git_error, last_error, with_opt_c_str, from_c_buf};
+use super::Repository;
+
+/// Priority level of a config file.
+/// These priority levels correspond to the natural escalation logic
+/// (from higher to lower) when searching for config entries in git.git.
+pub enum ConfigLevel {
+    /** System-wide on Windows, for compatibility with portable git */
+    GIT_CONFIG_LEVEL_PROGRAMDATA = 1,
+    /** System-wide configuration file; /etc/gitconfig on Linux systems */
+    GIT_CONFIG_LEVEL_SYSTEM = 2,
+    /** XDG compatible configuration file; typically ~/.config/git/config */
+    GIT_CONFIG_LEVEL_XDG = 3,
+    /** User-specific configuration file (also called Global configuration file);
+     *  typically ~/.gitconfig */
+    GIT_CONFIG_LEVEL_GLOBAL = 4,
+    /** Repository specific configuration file; $WORK_DIR/.git/config on
+     *  non-bare repos */
+    GIT_CONFIG_LEVEL_LOCAL = 5,
+    /** Application specific configuration file; freely defined by applications */
+    GIT_CONFIG_LEVEL_APP = 6,
+    /** Represents the highest level available config file (i.e. the most
+     *  specific config file available that actually is loaded) */
+    GIT_CONFIG_HIGHEST_LEVEL = -1,
+}
+
+fn level_from_c(level: c_int) -> ConfigLevel {
+    match level {
+        1 => GIT_CONFIG_LEVEL_PROGRAMDATA,
+        2 => GIT_CONFIG_LEVEL_SYSTEM,
+        3 => GIT_CONFIG_LEVEL_XDG,
+        4 => GIT_CONFIG_LEVEL_GLOBAL,
+        5 => GIT_CONFIG_LEVEL_LOCAL,
+        6 => GIT_CONFIG_LEVEL_APP,
+        _ => GIT_CONFIG_HIGHEST_LEVEL,
+    }
+}
+
+/// A single variable of a config file
+pub struct ConfigEntry {
+    name: ~str,
+    value: ~str,
+    /// Level of the file the variable was read from
+    level: ConfigLevel,
+}
+
+/// Iterator over the variables of a config
+pub struct ConfigEntries<'r> {
+    priv iter: *ext::git_config_iterator,
+    priv owner: &'r Config,
+}
+
+impl<'r> Iterator<ConfigEntry> for ConfigEntries<'r> {
+    fn next(&mut self) -> Option<ConfigEntry> {
+        unsafe {
+            let mut entry: *ext::git_config_entry = ptr::null();
+            match ext::git_config_next(&mut entry, self.iter) {
+                0 => Some( ConfigEntry {
+                    name: str::raw::from_c_str((*entry).name),
+                    value: str::raw::from_c_str((*entry).value),
+                    level: level_from_c((*entry).level),
+                }),
+                ext::GIT_ITEROVER => None,
+                _ => {
+                    git_error::cond.raise(last_error());
+                    None
+                },
+            }
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<'r> Drop for ConfigEntries<'r> {
+    fn drop(&mut self) {
+        unsafe {
+            ext::git_config_iterator_free(self.iter);
+        }
+    }
+}
+
+fn find_file(find: |&mut ext::git_buf| -> c_int) -> Option<~str> {
+    let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
+    unsafe {
+        if find(&mut buf) == 0 {
+            Some( from_c_buf(&mut buf) )
+        } else {
+            None
+        }
+    }
+}
+
+extern "C" fn multivar_cb(entry: *ext::git_config_entry, payload: *c_void) -> c_int
+{
+    unsafe {
+        let values: &mut ~[~str] = cast::transmute(payload);
+        values.push(str::raw::from_c_str((*entry).value));
+        0
+    }
+}
+
+/// A set of config files, each with its own priority level
+pub struct Config {
+    priv cfg: *ext::git_config,
+    priv snapshot: bool,
+}
+
+impl Config {
+    /// Allocate a new configuration object with no files attached.
+    /// Add files with `add_file_ondisk`.
+    pub fn new() -> Result<Config, (~str, GitError)>
+    {
+        super::global_init();
+        let mut cfg: *ext::git_config = ptr::null();
+        unsafe {
+            if ext::git_config_new(&mut cfg) == 0 {
+                Ok( Config { cfg: cfg, snapshot: false } )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Open the global, XDG and system configuration files.
+    ///
+    /// Utility wrapper that finds the global, XDG compatible and system
+    /// configuration files and opens them into a single prioritized config
+    /// object that can be used when accessing default config data outside a
+    /// repository.
+    pub fn open_default() -> Result<Config, (~str, GitError)>
+    {
+        super::global_init();
+        let mut cfg: *ext::git_config = ptr::null();
+        unsafe {
+            if ext::git_config_open_default(&mut cfg) == 0 {
+                Ok( Config { cfg: cfg, snapshot: false } )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Open a single config file, without a repository.
+    ///
+    /// The file is added at the GIT_CONFIG_LEVEL_LOCAL level, so writes go to it.
+    /// It is created on the first write if it does not exist.
+    /// This can edit any file in git config format, e.g. `.gitmodules`.
+    pub fn open_ondisk(path: &str) -> Result<Config, (~str, GitError)>
+    {
+        super::global_init();
+        let mut cfg: *ext::git_config = ptr::null();
+        path.with_c_str(|c_path| {
+            unsafe {
+                if ext::git_config_open_ondisk(&mut cfg, c_path) == 0 {
+                    Ok( Config { cfg: cfg, snapshot: false } )
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Create a read-only snapshot of the configuration
+    ///
+    /// The values of the snapshot are fixed at the time of the call, and
+    /// are not affected by later writes to the config files.
+    pub fn snapshot(&self) -> Result<Config, (~str, GitError)>
+    {
+        let mut cfg: *ext::git_config = ptr::null();
+        unsafe {
+            if ext::git_config_snapshot(&mut cfg, self.cfg) == 0 {
+                Ok( Config { cfg: cfg, snapshot: true } )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Parse a string value as a bool.
+    ///
+    /// Valid values for true are: 'true', 'yes', 'on', 1 or any
+    /// number different from 0.
+    /// Valid values for false are: 'false', 'no', 'off', 0
+    pub fn parse_bool(value: &str) -> Option<bool>
+    {
+        super::global_init();
+        let mut out: c_int = 0;
+        value.with_c_str(|c_value| {
+            unsafe {
+                if ext::git_config_parse_bool(&mut out, c_value) == 0 {
+                    Some(out != 0)
+                } else {
+                    None
+                }
+            }
+        })
+    }
+
+    /// Parse a string value as a 32-bit integer.
+    ///
+    /// An optional value suffix of 'k', 'm', or 'g' will cause the value
+    /// to be multiplied by 1024, 1048576, or 1073741824 prior to output.
+    pub fn parse_i32(value: &str) -> Option<i32>
+    {
+        let mut out: i32 = 0;
+        value.with_c_str(|c_value| {
+            unsafe {
+                if ext::git_config_parse_int32(&mut out, c_value) == 0 {
+                    Some(out)
+                } else {
+                    None
+                }
+            }
+        })
+    }
+
+    /// Parse a string value as a 64-bit integer.
+    ///
+    /// An optional value suffix of 'k', 'm', or 'g' will cause the value
+    /// to be multiplied by 1024, 1048576, or 1073741824 prior to output.
+    pub fn parse_i64(value: &str) -> Option<i64>
+    {
+        let mut out: i64 = 0;
+        value.with_c_str(|c_value| {
+            unsafe {
+                if ext::git_config_parse_int64(&mut out, c_value) == 0 {
+                    Some(out)
+                } else {
+                    None
+                }
+            }
+        })
+    }
+
+    /// Parse a string value as a path.
+    ///
+    /// A leading '~/' is expanded to the user's home directory.
+    /// A '~user/' prefix is not supported and yields None.
+    pub fn parse_path(value: &str) -> Option<~str>
+    {
+        super::global_init();
+        let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
+        value.with_c_str(|c_value| {
+            unsafe {
+                if ext::git_config_parse_path(&mut buf, c_value) == 0 {
+                    Some( from_c_buf(&mut buf) )
+                } else {
+                    None
+                }
+            }
+        })
+    }
+
+    /// Locate the path to the global configuration file (e.g. ~/.gitconfig)
+    pub fn find_global() -> Option<~str>
+    {
+        super::global_init();
+        find_file(|buf| unsafe { ext::git_config_find_global(buf) })
+    }
+
+    /// Locate the path to the XDG compatible configuration file
+    /// (e.g. ~/.config/git/config)
+    pub fn find_xdg() -> Option<~str>
+    {
+        super::global_init();
+        find_file(|buf| unsafe { ext::git_config_find_xdg(buf) })
+    }
+
+    /// Locate the path to the system configuration file (e.g. /etc/gitconfig)
+    pub fn find_system() -> Option<~str>
+    {
+        super::global_init();
+        find_file(|buf| unsafe { ext::git_config_find_system(buf) })
+    }
+
+    /// Add an on-disk config file instance to an existing config
+    ///
+    /// The on-disk file pointed at by `path` will be opened and
+    /// parsed; it's expected to be a native Git config file following
+    /// the default Git config syntax (see man git-config).
+    ///
+    /// If the file does not exist, it will be created on the first write.
+    ///
+    /// Further queries on this config object will access each
+    /// of the config file instances in order (instances with
+    /// a higher priority level will be accessed first).
+    ///
+    /// force: replace config file at the given priority level
+    pub fn add_file_ondisk(&self, path: &str, level: ConfigLevel, force: bool)
+        -> Result<(), (~str, GitError)>
+    {
+        path.with_c_str(|c_path| {
+            unsafe {
+                if ext::git_config_add_file_ondisk(self.cfg, c_path, level as c_int,
+                                                   ptr::null(), force as c_int) == 0 {
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Build a single-level focused config object from a multi-level one.
+    ///
+    /// The returned config object can be used to perform get/set/delete operations
+    /// on a single specific level.
+    pub fn open_level(&self, level: ConfigLevel) -> Result<Config, (~str, GitError)>
+    {
+        let mut cfg: *ext::git_config = ptr::null();
+        unsafe {
+            if ext::git_config_open_level(&mut cfg, self.cfg, level as c_int) == 0 {
+                Ok( Config { cfg: cfg, snapshot: self.snapshot } )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Get the value of a boolean config variable.
+    ///
+    /// All config files will be looked into, in the order of their
+    /// defined level. A higher level means a higher priority. The
+    /// first occurrence of the variable will be returned here.
+    ///
+    /// Returns None if the variable is not set.
+    /// raises git_error if the value cannot be parsed as a boolean
+    pub fn get_bool(&self, name: &str) -> Option<bool>
+    {
+        let mut out: c_int = 0;
+        name.with_c_str(|c_name| {
+            unsafe {
+                match ext::git_config_get_bool(&mut out, self.cfg, c_name) {
+                    0 => Some(out != 0),
+                    ext::GIT_ENOTFOUND => None,
+                    _ => {
+                        git_error::cond.raise(last_error());
+                        None
+                    },
+                }
+            }
+        })
+    }
+
+    /// Get the value of an integer config variable.
+    /// The value may have a `k`, `m` or `g` suffix.
+    ///
+    /// Returns None if the variable is not set.
+    /// raises git_error if the value cannot be parsed as a 32-bit integer
+    pub fn get_i32(&self, name: &str) -> Option<i32>
+    {
+        let mut out: i32 = 0;
+        name.with_c_str(|c_name| {
+            unsafe {
+                match ext::git_config_get_int32(&mut out, self.cfg, c_name) {
+                    0 => Some(out),
+                    ext::GIT_ENOTFOUND => None,
+                    _ => {
+                        git_error::cond.raise(last_error());
+                        None
+                    },
+                }
+            }
+        })
+    }
+
+    /// Get the value of a long integer config variable.
+    /// The value may have a `k`, `m` or `g` suffix.
+    ///
+    /// Returns None if the variable is not set.
+    /// raises git_error if the value cannot be parsed as a 64-bit integer
+    pub fn get_i64(&self, name: &str) -> Option<i64>
+    {
+        let mut out: i64 = 0;
+        name.with_c_str(|c_name| {
+            unsafe {
+                match ext::git_config_get_int64(&mut out, self.cfg, c_name) {
+                    0 => Some(out),
+                    ext::GIT_ENOTFOUND => None,
+                    _ => {
+                        git_error::cond.raise(last_error());
+                        None
+                    },
+                }
+            }
+        })
+    }
+
+    /// Get the value of a string config variable.
+    ///
+    /// Returns None if the variable is not set.
+    pub fn get_str(&self, name: &str) -> Option<~str>
+    {
+        name.with_c_str(|c_name| {
+            unsafe {
+                // only a snapshot keeps the string alive after the call returns
+                let res = if self.snapshot {
+                    let mut out: *c_char = ptr::null();
+                    let res = ext::git_config_get_string(&mut out, self.cfg, c_name);
+                    if res == 0 {
+                        return Some( str::raw::from_c_str(out) );
+                    }
+                    res
+                } else {
+                    let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
+                    let res = ext::git_config_get_string_buf(&mut buf, self.cfg, c_name);
+                    if res == 0 {
+                        return Some( from_c_buf(&mut buf) );
+                    }
+                    res
+                };
+                match res {
+                    ext::GIT_ENOTFOUND => None,
+                    _ => {
+                        git_error::cond.raise(last_error());
+                        None
+                    },
+                }
+            }
+        })
+    }
+
+    /// Get the value of a path config variable.
+    ///
+    /// A leading '~/' is expanded to the user's home directory.
+    ///
+    /// Returns None if the variable is not set.
+    pub fn get_path(&self, name: &str) -> Option<~str>
+    {
+        let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
+        name.with_c_str(|c_name| {
+            unsafe {
+                match ext::git_config_get_path(&mut buf, self.cfg, c_name) {
+                    0 => Some( from_c_buf(&mut buf) ),
+                    ext::GIT_ENOTFOUND => None,
+                    _ => {
+                        git_error::cond.raise(last_error());
+                        None
+                    },
+                }
+            }
+        })
+    }
+
+    /// Get all the values of a multivar, in the order they appear in the config files
+    ///
+    /// regexp: if given, only values matching this regular expression are returned
+    pub fn get_multivar(&self, name: &str, regexp: Option<&str>)
+        -> Result<~[~str], (~str, GitError)>
+    {
+        let mut values: ~[~str] = ~[];
+        let res = name.with_c_str(|c_name| {
+            with_opt_c_str(regexp, |c_regexp| {
+                unsafe {
+                    let payload: *c_void = cast::transmute(&mut values);
+                    ext::git_config_get_multivar_foreach(self.cfg, c_name, c_regexp,
+                                cast::transmute(multivar_cb), payload)
+                }
+            })
+        });
+        match res {
+            0 | ext::GIT_ENOTFOUND => Ok(values),
+            _ => unsafe { Err( last_error() ) },
+        }
+    }
+
+    /// Set the value of a boolean config variable in the config file
+    /// with the highest level (usually the local one).
+    pub fn set_bool(&self, name: &str, value: bool) -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            unsafe {
+                if ext::git_config_set_bool(self.cfg, c_name, value as c_int) == 0 {
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Set the value of an integer config variable in the config file
+    /// with the highest level (usually the local one).
+    pub fn set_i32(&self, name: &str, value: i32) -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            unsafe {
+                if ext::git_config_set_int32(self.cfg, c_name, value) == 0 {
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Set the value of a long integer config variable in the config file
+    /// with the highest level (usually the local one).
+    pub fn set_i64(&self, name: &str, value: i64) -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            unsafe {
+                if ext::git_config_set_int64(self.cfg, c_name, value) == 0 {
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Set the value of a string config variable in the config file
+    /// with the highest level (usually the local one).
+    pub fn set_str(&self, name: &str, value: &str) -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            value.with_c_str(|c_value| {
+                unsafe {
+                    if ext::git_config_set_string(self.cfg, c_name, c_value) == 0 {
+                        Ok(())
+                    } else {
+                        Err( last_error() )
+                    }
+                }
+            })
+        })
+    }
+
+    /// Set a multivar in the local config file.
+    ///
+    /// The values matching `regexp` are replaced by `value`.
+    /// If no value matches, `value` is added as a new value of the multivar.
+    pub fn set_multivar(&self, name: &str, regexp: &str, value: &str)
+        -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            regexp.with_c_str(|c_regexp| {
+                value.with_c_str(|c_value| {
+                    unsafe {
+                        if ext::git_config_set_multivar(self.cfg, c_name, c_regexp,
+                                                        c_value) == 0 {
+                            Ok(())
+                        } else {
+                            Err( last_error() )
+                        }
+                    }
+                })
+            })
+        })
+    }
+
+    /// Delete a config variable from the config file
+    /// with the highest level (usually the local one).
+    pub fn delete_entry(&self, name: &str) -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            unsafe {
+                if ext::git_config_delete_entry(self.cfg, c_name) == 0 {
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Delete the values of a multivar matching `regexp` from the config file
+    /// with the highest level (usually the local one).
+    pub fn delete_multivar(&self, name: &str, regexp: &str) -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            regexp.with_c_str(|c_regexp| {
+                unsafe {
+                    if ext::git_config_delete_multivar(self.cfg, c_name, c_regexp) == 0 {
+                        Ok(())
+                    } else {
+                        Err( last_error() )
+                    }
+                }
+            })
+        })
+    }
+
+    /// Iterate over the config variables of every level
+    ///
+    /// glob: if given, only variables whose name matches this regular expression
+    /// are returned, e.g. `^ourteam\.`
+    pub fn entries<'r>(&'r self, glob: Option<&str>)
+        -> Result<ConfigEntries<'r>, (~str, GitError)>
+    {
+        let mut iter: *ext::git_config_iterator = ptr::null();
+        unsafe {
+            let res = match glob {
+                None => ext::git_config_iterator_new(&mut iter, self.cfg),
+                Some(g) => g.with_c_str(|c_glob| {
+                    ext::git_config_iterator_glob_new(&mut iter, self.cfg, c_glob)
+                }),
+            };
+            if res == 0 {
+                Ok( ConfigEntries { iter: iter, owner: self } )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+}
+
+impl Drop for Config {
+    fn drop(&mut self) {
+        unsafe {
+            ext::git_config_free(self.cfg);
+        }
+    }
+}
+
+impl Repository {
+    /// Get the configuration file for this repository.
+    ///
+    /// If a configuration file has not been set, the default
+    /// config set for the repository will be returned, including
+    /// global and system configurations (if they are available).
+    pub fn config(&self) -> Result<Config, (~str, GitError)>
+    {
+        let mut cfg: *ext::git_config = ptr::null();
+        unsafe {
+            if ext::git_repository_config(&mut cfg, self.repo) == 0 {
+                Ok( Config { cfg: cfg, snapshot: false } )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+}
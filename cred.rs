@@ -0,0 +1,269 @@
+use std::{vec, str, run};
+use std::libc::{c_int, c_uint};
+use ext;
+use super::with_opt_c_str;
+use super::Config;
+
+/// Kinds of credentials a transport may ask for
+#[deriving(Eq)]
+pub enum CredType {
+    /** A username and password in plain text */
+    GIT_CREDTYPE_USERPASS_PLAINTEXT = (1 << 0),
+    /** An SSH key pair read from files */
+    GIT_CREDTYPE_SSH_KEY = (1 << 1),
+    /** An SSH key with a custom signing function */
+    GIT_CREDTYPE_SSH_CUSTOM = (1 << 2),
+    /** The current user's NTLM or Negotiate credentials */
+    GIT_CREDTYPE_DEFAULT = (1 << 3),
+    /** SSH keyboard-interactive authentication */
+    GIT_CREDTYPE_SSH_INTERACTIVE = (1 << 4),
+    /** Only a username, e.g. when an SSH transport asks which user to authenticate as */
+    GIT_CREDTYPE_USERNAME = (1 << 5),
+    /** An SSH key pair held in memory */
+    GIT_CREDTYPE_SSH_MEMORY = (1 << 6),
+}
+
+static ALL_CREDTYPES: [CredType, .. 7] = [
+    GIT_CREDTYPE_USERPASS_PLAINTEXT,
+    GIT_CREDTYPE_SSH_KEY,
+    GIT_CREDTYPE_SSH_CUSTOM,
+    GIT_CREDTYPE_DEFAULT,
+    GIT_CREDTYPE_SSH_INTERACTIVE,
+    GIT_CREDTYPE_USERNAME,
+    GIT_CREDTYPE_SSH_MEMORY,
+];
+
+pub fn from_c_credtypes(allowed: c_uint) -> ~[CredType] {
+    ALL_CREDTYPES.iter().filter(|&t| allowed & (*t as c_uint) != 0).map(|&t| t).collect()
+}
+
+/// Credentials handed to a transport that asks for them
+pub enum Cred {
+    /// username, password
+    CredUserPassPlaintext(~str, ~str),
+    /// username, public key file, private key file, passphrase.
+    /// The public key is derived from the private one if not given.
+    CredSshKey(~str, Option<~str>, ~str, Option<~str>),
+    /// username, public key, private key, passphrase, with the keys given by content
+    CredSshKeyMemory(~str, Option<~str>, ~str, Option<~str>),
+    /// username; the key is requested from a running ssh-agent
+    CredSshAgent(~str),
+    /// the current user's credentials, for NTLM or Negotiate authentication
+    CredDefault,
+    /// username only
+    CredUsername(~str),
+}
+
+impl Cred {
+    /// The kind of credentials this is
+    pub fn cred_type(&self) -> CredType
+    {
+        match *self {
+            CredUserPassPlaintext(..) => GIT_CREDTYPE_USERPASS_PLAINTEXT,
+            CredSshKey(..) => GIT_CREDTYPE_SSH_KEY,
+            CredSshKeyMemory(..) => GIT_CREDTYPE_SSH_MEMORY,
+            CredSshAgent(..) => GIT_CREDTYPE_SSH_KEY,
+            CredDefault => GIT_CREDTYPE_DEFAULT,
+            CredUsername(..) => GIT_CREDTYPE_USERNAME,
+        }
+    }
+
+    /// Create the libgit2 credential object; the transport takes ownership of it.
+    pub unsafe fn to_c_cred(&self, out: &mut *ext::git_credential) -> c_int
+    {
+        match *self {
+            CredUserPassPlaintext(ref username, ref password) => {
+                username.with_c_str(|c_username| {
+                    password.with_c_str(|c_password| {
+                        ext::git_credential_userpass_plaintext_new(out, c_username, c_password)
+                    })
+                })
+            },
+            CredSshKey(ref username, ref publickey, ref privatekey, ref passphrase) => {
+                username.with_c_str(|c_username| {
+                    with_opt_c_str(publickey.as_ref().map(|s| s.as_slice()), |c_publickey| {
+                        privatekey.with_c_str(|c_privatekey| {
+                            with_opt_c_str(passphrase.as_ref().map(|s| s.as_slice()),
+                                           |c_passphrase| {
+                                ext::git_credential_ssh_key_new(out, c_username, c_publickey,
+                                                                c_privatekey, c_passphrase)
+                            })
+                        })
+                    })
+                })
+            },
+            CredSshKeyMemory(ref username, ref publickey, ref privatekey, ref passphrase) => {
+                username.with_c_str(|c_username| {
+                    with_opt_c_str(publickey.as_ref().map(|s| s.as_slice()), |c_publickey| {
+                        privatekey.with_c_str(|c_privatekey| {
+                            with_opt_c_str(passphrase.as_ref().map(|s| s.as_slice()),
+                                           |c_passphrase| {
+                                ext::git_credential_ssh_key_memory_new(out, c_username,
+                                                                       c_publickey,
+                                                                       c_privatekey,
+                                                                       c_passphrase)
+                            })
+                        })
+                    })
+                })
+            },
+            CredSshAgent(ref username) => {
+                username.with_c_str(|c_username| {
+                    ext::git_credential_ssh_key_from_agent(out, c_username)
+                })
+            },
+            CredDefault => ext::git_credential_default_new(out),
+            CredUsername(ref username) => {
+                username.with_c_str(|c_username| {
+                    ext::git_credential_username_new(out, c_username)
+                })
+            },
+        }
+    }
+
+    /// Ask the helpers configured in `credential.helper` for a username and
+    /// password for `url`, the way git does.
+    ///
+    /// Helpers are tried in order until one answers with a password. An empty
+    /// `credential.helper` value resets the list of helpers configured before it.
+    /// A helper starting with `!` is run as a shell command, an absolute path
+    /// is run as is, and any other name `foo` runs `git credential-foo`.
+    ///
+    /// username: the username to ask a password for, e.g. the one from the URL.
+    ///  If None, the helper must provide the username as well.
+    pub fn credential_helper(config: &Config, url: &str, username: Option<&str>)
+        -> Option<Cred>
+    {
+        let helpers = match config.get_multivar("credential.helper", None) {
+            Ok(h) => h,
+            Err(_) => return None,
+        };
+        let (protocol, host, path) = match split_url(url) {
+            Some(parts) => parts,
+            None => return None,
+        };
+
+        let mut input = format!("protocol={}\nhost={}\n", protocol, host);
+        if !path.is_empty() {
+            input.push_str(format!("path={}\n", path));
+        }
+        match username {
+            Some(u) => input.push_str(format!("username={}\n", u)),
+            None => (),
+        }
+        input.push_str("\n");
+
+        // as in git, an empty value drops the helpers configured before it
+        let start = match helpers.iter().rposition(|h| h.is_empty()) {
+            Some(idx) => idx + 1,
+            None => 0,
+        };
+        for helper in helpers.slice_from(start).iter() {
+            let cmd = if helper.starts_with("!") {
+                helper.slice_from(1).to_owned()
+            } else if helper.starts_with("/") {
+                helper.clone()
+            } else {
+                format!("git credential-{}", *helper)
+            };
+            let (user, password) = run_helper(cmd, input);
+            let user = user.or(username.map(|u| u.to_owned()));
+            match (user, password) {
+                (Some(u), Some(p)) => return Some( CredUserPassPlaintext(u, p) ),
+                _ => (),
+            }
+        }
+        None
+    }
+}
+
+// split "proto://[user@]host[:port]/path" into (proto, host[:port], path)
+fn split_url<'a>(url: &'a str) -> Option<(&'a str, &'a str, &'a str)> {
+    let sep = match url.find_str("://") {
+        Some(i) => i,
+        None => return None,
+    };
+    let protocol = url.slice_to(sep);
+    let rest = url.slice_from(sep + 3);
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (rest.slice_to(i), rest.slice_from(i + 1)),
+        None => (rest, ""),
+    };
+    let host = match authority.rfind('@') {
+        Some(i) => authority.slice_from(i + 1),
+        None => authority,
+    };
+    Some( (protocol, host, path) )
+}
+
+// run `<cmd> get` through the shell, feeding it `input`, and read back
+// the username and password it answers with
+fn run_helper(cmd: &str, input: &str) -> (Option<~str>, Option<~str>) {
+    let args = [~"-c", format!("{} \"$@\"", cmd), cmd.to_owned(), ~"get"];
+    let mut process = match run::Process::new("sh", args, run::ProcessOptions::new()) {
+        Some(p) => p,
+        None => return (None, None),
+    };
+    process.input().write(input.as_bytes());
+    process.close_input();
+    let output = process.finish_with_output();
+    if !output.status.success() {
+        return (None, None);
+    }
+
+    let mut username = None;
+    let mut password = None;
+    for line in str::from_utf8(output.output).lines() {
+        match line.find('=') {
+            Some(i) => {
+                let value = line.slice_from(i + 1).to_owned();
+                match line.slice_to(i) {
+                    "username" => username = Some(value),
+                    "password" => password = Some(value),
+                    _ => (),
+                }
+            },
+            None => (),
+        }
+    }
+    (username, password)
+}
+
+/// A certificate presented by a remote
+pub enum Cert {
+    /// The DER-encoded X.509 certificate of an HTTPS server
+    CertX509(~[u8]),
+    /// The MD5, SHA-1 and SHA-256 hashes of an SSH server's host key, when available
+    CertHostkey(Option<~[u8]>, Option<~[u8]>, Option<~[u8]>),
+    /// A kind of certificate this library cannot represent
+    CertOther,
+}
+
+pub unsafe fn from_c_cert(cert: *ext::git_cert) -> Cert {
+    match (*cert).cert_type {
+        ext::GIT_CERT_X509 => {
+            let x509: *ext::git_cert_x509 = cert as *ext::git_cert_x509;
+            CertX509( vec::raw::from_buf_raw((*x509).data as *u8, (*x509).len as uint) )
+        },
+        ext::GIT_CERT_HOSTKEY_LIBSSH2 => {
+            let hostkey: *ext::git_cert_hostkey = cert as *ext::git_cert_hostkey;
+            let md5 = if (*hostkey).hash_type & ext::GIT_CERT_SSH_MD5 != 0 {
+                Some( (*hostkey).hash_md5.to_owned() )
+            } else {
+                None
+            };
+            let sha1 = if (*hostkey).hash_type & ext::GIT_CERT_SSH_SHA1 != 0 {
+                Some( (*hostkey).hash_sha1.to_owned() )
+            } else {
+                None
+            };
+            let sha256 = if (*hostkey).hash_type & ext::GIT_CERT_SSH_SHA256 != 0 {
+                Some( (*hostkey).hash_sha256.to_owned() )
+            } else {
+                None
+            };
+            CertHostkey(md5, sha1, sha256)
+        },
+        _ => CertOther,
+    }
+}
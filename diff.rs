@@ -1,5 +1,7 @@
 use ext;
-use super::{OID, DiffList};
+use std::str;
+use std::libc::size_t;
+use super::{OID, DiffList, DiffDelta};
 
 pub enum DiffFlag {
     /** Reverse the sides of the diff */
@@ -111,11 +113,44 @@ pub struct DiffFile {
     mode: u16,
 }
 
+unsafe fn from_c_diff_file(c_file: &ext::git_diff_file) -> DiffFile {
+    DiffFile {
+        oid: c_file.id,
+        path: str::raw::from_c_str(c_file.path),
+        size: c_file.size as i64,
+        flags: c_file.flags,
+        mode: c_file.mode,
+    }
+}
+
+impl DiffList {
+    /// Query how many deltas (changed files) are in the diff list
+    pub fn num_deltas(&self) -> uint {
+        unsafe {
+            ext::git_diff_num_deltas(self.difflist) as uint
+        }
+    }
+
+    /// Return the status and the old and new file of the delta at `idx`,
+    /// or None if `idx` is out of range
+    pub fn delta(&self, idx: uint) -> Option<(DiffDelta, DiffFile, DiffFile)> {
+        unsafe {
+            let c_delta = ext::git_diff_get_delta(self.difflist, idx as size_t);
+            if c_delta.is_null() {
+                None
+            } else {
+                Some( ((*c_delta).status, from_c_diff_file(&(*c_delta).old_file),
+                       from_c_diff_file(&(*c_delta).new_file)) )
+            }
+        }
+    }
+}
+
 #[unsafe_destructor]
 impl Drop for DiffList {
     fn drop(&mut self) {
         unsafe {
-            ext::git_diff_list_free(self.difflist);
+            ext::git_diff_free(self.difflist);
         }
     }
 }
@@ -15,26 +15,133 @@ pub static GIT_EAMBIGUOUS:c_int = -5;
 pub static GIT_EBUFS:c_int = -6;
 pub static GIT_EUSER:c_int = -7;
 pub static GIT_EBAREREPO:c_int = -8;
-pub static GIT_EORPHANEDHEAD:c_int = -9;
+pub static GIT_EUNBORNBRANCH:c_int = -9;
 pub static GIT_EUNMERGED:c_int = -10;
 pub static GIT_ENONFASTFORWARD:c_int = -11;
 pub static GIT_EINVALIDSPEC:c_int = -12;
-pub static GIT_EMERGECONFLICT:c_int = -13;
+pub static GIT_ECONFLICT:c_int = -13;
+pub static GIT_ELOCKED:c_int = -14;
+pub static GIT_EMODIFIED:c_int = -15;
+pub static GIT_EAUTH:c_int = -16;
+pub static GIT_ECERTIFICATE:c_int = -17;
+pub static GIT_EAPPLIED:c_int = -18;
+pub static GIT_EPEEL:c_int = -19;
+pub static GIT_EEOF:c_int = -20;
+pub static GIT_EINVALID:c_int = -21;
+pub static GIT_EUNCOMMITTED:c_int = -22;
+pub static GIT_EDIRECTORY:c_int = -23;
+pub static GIT_EMERGECONFLICT:c_int = -24;
 
 pub static GIT_PASSTHROUGH:c_int = -30;
 pub static GIT_ITEROVER:c_int = -31;
+pub static GIT_RETRY:c_int = -32;
+pub static GIT_EMISMATCH:c_int = -33;
+pub static GIT_EINDEXDIRTY:c_int = -34;
+pub static GIT_EAPPLYFAIL:c_int = -35;
 
 /* from <git2/remote.h> */
+#[deriving(Eq)]
 pub enum git_remote_autotag_option_t {
-    GIT_REMOTE_DOWNLOAD_TAGS_UNSET,
-    GIT_REMOTE_DOWNLOAD_TAGS_NONE,
+    GIT_REMOTE_DOWNLOAD_TAGS_UNSPECIFIED,
     GIT_REMOTE_DOWNLOAD_TAGS_AUTO,
+    GIT_REMOTE_DOWNLOAD_TAGS_NONE,
     GIT_REMOTE_DOWNLOAD_TAGS_ALL
 }
 
-/* from <git2/transport.h> */
+/* from <git2/indexer.h> */
+pub struct git_indexer_progress {
+    total_objects: c_uint,
+    indexed_objects: c_uint,
+    received_objects: c_uint,
+    local_objects: c_uint,
+    total_deltas: c_uint,
+    indexed_deltas: c_uint,
+    received_bytes: size_t,
+}
+
+/* from <git2/remote.h> */
+pub static GIT_REMOTE_CALLBACKS_VERSION:c_uint = 1;
+
+pub struct git_remote_callbacks {
+    version: c_uint,
+    sideband_progress: callback_t,
+    completion: callback_t,
+    credentials: callback_t,
+    certificate_check: callback_t,
+    transfer_progress: callback_t,
+    update_tips: callback_t,
+    pack_progress: callback_t,
+    push_transfer_progress: callback_t,
+    push_update_reference: callback_t,
+    push_negotiation: callback_t,
+    transport: callback_t,
+    remote_ready: callback_t,
+    payload: *c_void,
+    resolve_url: callback_t,
+}
+
+pub type git_fetch_prune_t = c_uint;
+
+pub static GIT_FETCH_OPTIONS_VERSION:c_int = 1;
+
+pub struct git_fetch_options {
+    version: c_int,
+    callbacks: git_remote_callbacks,
+    prune: git_fetch_prune_t,
+    update_fetchhead: c_int,
+    download_tags: git_remote_autotag_option_t,
+    proxy_opts: git_proxy_options,
+    custom_headers: git_strarray,
+}
+
+pub type git_direction = c_uint;
+
+pub static GIT_DIRECTION_FETCH: git_direction = 0;
+pub static GIT_DIRECTION_PUSH: git_direction = 1;
+
+pub struct git_remote_head {
+    local: c_int,
+    oid: super::OID,
+    loid: super::OID,
+    name: *c_char,
+    symref_target: *c_char,
+}
+
+pub static GIT_PUSH_OPTIONS_VERSION:c_uint = 1;
+
+pub struct git_push_options {
+    version: c_uint,
+    pb_parallelism: c_uint,
+    callbacks: git_remote_callbacks,
+    proxy_opts: git_proxy_options,
+    custom_headers: git_strarray,
+}
+
+/* from <git2/proxy.h> */
+pub type git_proxy_t = c_uint;
+
+pub static GIT_PROXY_NONE: git_proxy_t = 0;
+pub static GIT_PROXY_AUTO: git_proxy_t = 1;
+pub static GIT_PROXY_SPECIFIED: git_proxy_t = 2;
+
+pub static GIT_PROXY_OPTIONS_VERSION:c_uint = 1;
+
+pub struct git_proxy_options {
+    version: c_uint,
+    proxy_type: git_proxy_t,
+    url: *c_char,
+    credentials: callback_t,
+    certificate_check: callback_t,
+    payload: *c_void,
+}
+
+/* from <git2/sys/transport.h> */
+pub static GIT_TRANSPORT_VERSION:c_uint = 1;
+
 pub struct git_transport {
+    version: c_uint,
     set_callbacks: callback_t,
+    set_custom_headers: callback_t,
     connect: callback_t,
     ls: callback_t,
     push: callback_t,
@@ -47,6 +154,39 @@ pub struct git_transport {
     free: callback_t,
 }
 
+pub type git_smart_service_t = c_uint;
+
+pub static GIT_SERVICE_UPLOADPACK_LS: git_smart_service_t = 1;
+pub static GIT_SERVICE_UPLOADPACK: git_smart_service_t = 2;
+pub static GIT_SERVICE_RECEIVEPACK_LS: git_smart_service_t = 3;
+pub static GIT_SERVICE_RECEIVEPACK: git_smart_service_t = 4;
+
+pub struct git_smart_subtransport_stream {
+    subtransport: *git_smart_subtransport,
+    read: callback_t,
+    write: callback_t,
+    free: callback_t,
+}
+
+pub struct git_smart_subtransport {
+    action: callback_t,
+    close: callback_t,
+    free: callback_t,
+}
+
+pub struct git_smart_subtransport_definition {
+    callback: callback_t,
+    rpc: c_uint,
+    param: *c_void,
+}
+
+/* from <git2/sys/stream.h> */
+pub struct git_writestream {
+    write: callback_t,
+    close: callback_t,
+    free: callback_t,
+}
+
 /* from <git2/strarray.h> */
 pub struct git_strarray {
     strings: **c_char,
@@ -54,71 +194,87 @@ pub struct git_strarray {
 }
 
 /* from <git2/checkout.h> */
-type git_checkout_strategy_t = uint;
+type git_checkout_strategy_t = c_uint;
 
 /** default is a dry run, no actual updates */
 static GIT_CHECKOUT_NONE:git_checkout_strategy_t = 0;
 
 /** Allow safe updates that cannot overwrite uncommitted data */
-static GIT_CHECKOUT_SAFE:git_checkout_strategy_t = (1u << 0);
-
-/** Allow safe updates plus creation of missing files */
-static GIT_CHECKOUT_SAFE_CREATE:git_checkout_strategy_t = (1u << 1);
+static GIT_CHECKOUT_SAFE:git_checkout_strategy_t = (1u << 0) as c_uint;
 
 /** Allow all updates to force working directory to look like index */
-static GIT_CHECKOUT_FORCE:git_checkout_strategy_t = (1u << 2);
+static GIT_CHECKOUT_FORCE:git_checkout_strategy_t = (1u << 1) as c_uint;
+
+/** Allow checkout to recreate missing files */
+static GIT_CHECKOUT_RECREATE_MISSING:git_checkout_strategy_t = (1u << 2) as c_uint;
 
 /** Allow checkout to make safe updates even if conflicts are found */
-static GIT_CHECKOUT_ALLOW_CONFLICTS:git_checkout_strategy_t = (1u << 4);
+static GIT_CHECKOUT_ALLOW_CONFLICTS:git_checkout_strategy_t = (1u << 4) as c_uint;
 
 /** Remove untracked files not in index (that are not ignored) */
-static GIT_CHECKOUT_REMOVE_UNTRACKED:git_checkout_strategy_t = (1u << 5);
+static GIT_CHECKOUT_REMOVE_UNTRACKED:git_checkout_strategy_t = (1u << 5) as c_uint;
 
 /** Remove ignored files not in index */
-static GIT_CHECKOUT_REMOVE_IGNORED:git_checkout_strategy_t = (1u << 6);
+static GIT_CHECKOUT_REMOVE_IGNORED:git_checkout_strategy_t = (1u << 6) as c_uint;
 
 /** Only update existing files, don't create new ones */
-static GIT_CHECKOUT_UPDATE_ONLY:git_checkout_strategy_t = (1u << 7);
+static GIT_CHECKOUT_UPDATE_ONLY:git_checkout_strategy_t = (1u << 7) as c_uint;
 
 /** Normally checkout updates index entries as it goes; this stops that */
-static GIT_CHECKOUT_DONT_UPDATE_INDEX:git_checkout_strategy_t = (1u << 8);
+static GIT_CHECKOUT_DONT_UPDATE_INDEX:git_checkout_strategy_t = (1u << 8) as c_uint;
 
 /** Don't refresh index/config/etc before doing checkout */
-static GIT_CHECKOUT_NO_REFRESH:git_checkout_strategy_t = (1u << 9);
+static GIT_CHECKOUT_NO_REFRESH:git_checkout_strategy_t = (1u << 9) as c_uint;
+
+/** Allow checkout to skip unmerged files */
+static GIT_CHECKOUT_SKIP_UNMERGED:git_checkout_strategy_t = (1u << 10) as c_uint;
+/** For unmerged files, checkout stage 2 from index */
+static GIT_CHECKOUT_USE_OURS:git_checkout_strategy_t = (1u << 11) as c_uint;
+/** For unmerged files, checkout stage 3 from index */
+static GIT_CHECKOUT_USE_THEIRS:git_checkout_strategy_t = (1u << 12) as c_uint;
 
 /** Treat pathspec as simple list of exact match file paths */
-static GIT_CHECKOUT_DISABLE_PATHSPEC_MATCH:git_checkout_strategy_t = (1u << 13);
+static GIT_CHECKOUT_DISABLE_PATHSPEC_MATCH:git_checkout_strategy_t = (1u << 13) as c_uint;
 
 /** Ignore directories in use, they will be left empty */
-static GIT_CHECKOUT_SKIP_LOCKED_DIRECTORIES:git_checkout_strategy_t = (1u << 18);
+static GIT_CHECKOUT_SKIP_LOCKED_DIRECTORIES:git_checkout_strategy_t = (1u << 18) as c_uint;
+
+/** Don't overwrite ignored files that exist in the checkout target */
+static GIT_CHECKOUT_DONT_OVERWRITE_IGNORED:git_checkout_strategy_t = (1u << 19) as c_uint;
+
+/** Write normal merge files for conflicts */
+static GIT_CHECKOUT_CONFLICT_STYLE_MERGE:git_checkout_strategy_t = (1u << 20) as c_uint;
+
+/** Include common ancestor data in diff3 format files for conflicts */
+static GIT_CHECKOUT_CONFLICT_STYLE_DIFF3:git_checkout_strategy_t = (1u << 21) as c_uint;
 
-/**
- * THE FOLLOWING OPTIONS ARE NOT YET IMPLEMENTED
- */
+/** Don't overwrite existing files or folders */
+static GIT_CHECKOUT_DONT_REMOVE_EXISTING:git_checkout_strategy_t = (1u << 22) as c_uint;
 
-/** Allow checkout to skip unmerged files (NOT IMPLEMENTED) */
-static GIT_CHECKOUT_SKIP_UNMERGED:git_checkout_strategy_t = (1u << 10);
-/** For unmerged files, checkout stage 2 from index (NOT IMPLEMENTED) */
-static GIT_CHECKOUT_USE_OURS:git_checkout_strategy_t = (1u << 11);
-/** For unmerged files, checkout stage 3 from index (NOT IMPLEMENTED) */
-static GIT_CHECKOUT_USE_THEIRS:git_checkout_strategy_t = (1u << 12);
+/** Normally checkout writes the index upon completion; this prevents that */
+static GIT_CHECKOUT_DONT_WRITE_INDEX:git_checkout_strategy_t = (1u << 23) as c_uint;
+
+/** Show what would be done by a checkout, without making any changes */
+static GIT_CHECKOUT_DRY_RUN:git_checkout_strategy_t = (1u << 24) as c_uint;
 
 /** Recursively checkout submodules with same options (NOT IMPLEMENTED) */
-static GIT_CHECKOUT_UPDATE_SUBMODULES:git_checkout_strategy_t = (1u << 16);
+static GIT_CHECKOUT_UPDATE_SUBMODULES:git_checkout_strategy_t = (1u << 16) as c_uint;
 /** Recursively checkout submodules if HEAD moved in super repo (NOT IMPLEMENTED) */
-static GIT_CHECKOUT_UPDATE_SUBMODULES_IF_CHANGED:git_checkout_strategy_t = (1u << 17);
+static GIT_CHECKOUT_UPDATE_SUBMODULES_IF_CHANGED:git_checkout_strategy_t = (1u << 17) as c_uint;
 
-type git_checkout_notify_t = uint;
+type git_checkout_notify_t = c_uint;
 
 static GIT_CHECKOUT_NOTIFY_NONE:git_checkout_notify_t       = 0;
-static GIT_CHECKOUT_NOTIFY_CONFLICT:git_checkout_notify_t   = (1u << 0);
-static GIT_CHECKOUT_NOTIFY_DIRTY:git_checkout_notify_t      = (1u << 1);
-static GIT_CHECKOUT_NOTIFY_UPDATED:git_checkout_notify_t    = (1u << 2);
-static GIT_CHECKOUT_NOTIFY_UNTRACKED:git_checkout_notify_t  = (1u << 3);
-static GIT_CHECKOUT_NOTIFY_IGNORED:git_checkout_notify_t    = (1u << 4);
+static GIT_CHECKOUT_NOTIFY_CONFLICT:git_checkout_notify_t   = (1u << 0) as c_uint;
+static GIT_CHECKOUT_NOTIFY_DIRTY:git_checkout_notify_t      = (1u << 1) as c_uint;
+static GIT_CHECKOUT_NOTIFY_UPDATED:git_checkout_notify_t    = (1u << 2) as c_uint;
+static GIT_CHECKOUT_NOTIFY_UNTRACKED:git_checkout_notify_t  = (1u << 3) as c_uint;
+static GIT_CHECKOUT_NOTIFY_IGNORED:git_checkout_notify_t    = (1u << 4) as c_uint;
 
 /* from <git2/checkout.h> */
-pub struct git_checkout_opts {
+pub static GIT_CHECKOUT_OPTIONS_VERSION:c_uint = 1;
+
+pub struct git_checkout_options {
     version: c_uint,
 
     checkout_strategy: git_checkout_strategy_t,
@@ -138,27 +294,185 @@ pub struct git_checkout_opts {
     paths: git_strarray,
 
     baseline: *git_tree,
+    baseline_index: *git_index,
+
+    target_directory: *c_char,
+
+    ancestor_label: *c_char,
+    our_label: *c_char,
+    their_label: *c_char,
+
+    perfdata_cb: callback_t,
+    perfdata_payload: *c_void,
+}
+
+/* from <git2/credential.h> */
+pub type git_credential_t = c_uint;
+
+pub static GIT_CREDENTIAL_USERPASS_PLAINTEXT: git_credential_t = (1u << 0) as c_uint;
+pub static GIT_CREDENTIAL_SSH_KEY: git_credential_t = (1u << 1) as c_uint;
+pub static GIT_CREDENTIAL_SSH_CUSTOM: git_credential_t = (1u << 2) as c_uint;
+pub static GIT_CREDENTIAL_DEFAULT: git_credential_t = (1u << 3) as c_uint;
+pub static GIT_CREDENTIAL_SSH_INTERACTIVE: git_credential_t = (1u << 4) as c_uint;
+pub static GIT_CREDENTIAL_USERNAME: git_credential_t = (1u << 5) as c_uint;
+pub static GIT_CREDENTIAL_SSH_MEMORY: git_credential_t = (1u << 6) as c_uint;
+
+/* from <git2/cert.h> */
+pub type git_cert_t = c_int;
+
+pub static GIT_CERT_NONE: git_cert_t = 0;
+pub static GIT_CERT_X509: git_cert_t = 1;
+pub static GIT_CERT_HOSTKEY_LIBSSH2: git_cert_t = 2;
+pub static GIT_CERT_STRARRAY: git_cert_t = 3;
+
+pub struct git_cert {
+    cert_type: git_cert_t,
+}
+
+pub struct git_cert_x509 {
+    parent: git_cert,
+    data: *c_void,
+    len: size_t,
+}
+
+pub static GIT_CERT_SSH_MD5: c_uint = (1u << 0) as c_uint;
+pub static GIT_CERT_SSH_SHA1: c_uint = (1u << 1) as c_uint;
+pub static GIT_CERT_SSH_SHA256: c_uint = (1u << 2) as c_uint;
+pub static GIT_CERT_SSH_RAW: c_uint = (1u << 3) as c_uint;
+
+pub struct git_cert_hostkey {
+    parent: git_cert,
+    hash_type: c_uint,
+    hash_md5: [u8, .. 16],
+    hash_sha1: [u8, .. 20],
+    hash_sha256: [u8, .. 32],
+    raw_type: c_uint,
+    hostkey: *c_char,
+    hostkey_len: size_t,
 }
 
 /* from <git2/clone.h> */
+pub static GIT_CLONE_OPTIONS_VERSION:c_uint = 1;
+
+pub type git_clone_local_t = c_uint;
+
+pub static GIT_CLONE_LOCAL_AUTO: git_clone_local_t = 0;
+
 pub struct git_clone_options {
     version: c_uint,
 
-    checkout_opts: git_checkout_opts,
+    checkout_opts: git_checkout_options,
+    fetch_opts: git_fetch_options,
+
     bare: c_int,
-    fetch_progress_cb: callback_t,
-    fetch_progress_payload: *c_void,
-
-    remote_name: *c_char,
-    pushurl: *c_char,
-    fetch_spec: *c_char,
-    push_spec: *c_char,
-    cred_acquire_cb: callback_t,
-    cred_acquire_payload: *c_void,
-    transport: *git_transport,
-    remote_callbacks: callback_t,
-    remote_autotag: git_remote_autotag_option_t,
+    local: git_clone_local_t,
     checkout_branch: *c_char,
+
+    repository_cb: callback_t,
+    repository_cb_payload: *c_void,
+
+    remote_cb: callback_t,
+    remote_cb_payload: *c_void,
+}
+
+/* from <git2/merge.h> */
+pub static GIT_MERGE_OPTIONS_VERSION:c_uint = 1;
+
+pub static GIT_MERGE_ANALYSIS_NONE:c_uint = 0;
+pub static GIT_MERGE_ANALYSIS_NORMAL:c_uint = (1u << 0) as c_uint;
+pub static GIT_MERGE_ANALYSIS_UP_TO_DATE:c_uint = (1u << 1) as c_uint;
+pub static GIT_MERGE_ANALYSIS_FASTFORWARD:c_uint = (1u << 2) as c_uint;
+pub static GIT_MERGE_ANALYSIS_UNBORN:c_uint = (1u << 3) as c_uint;
+
+pub struct git_merge_options {
+    version: c_uint,
+    flags: u32,
+
+    rename_threshold: c_uint,
+    target_limit: c_uint,
+    metric: *c_void,
+
+    recursion_limit: c_uint,
+    default_driver: *c_char,
+
+    file_favor: c_uint,
+    file_flags: u32,
+}
+
+/* from <git2/buffer.h> */
+pub struct git_buf {
+    ptr: *c_char,
+    asize: size_t,
+    size: size_t,
+}
+
+/* from <git2/filter.h> */
+pub type git_filter_mode_t = c_uint;
+
+pub static GIT_FILTER_TO_WORKTREE: git_filter_mode_t = 0;
+pub static GIT_FILTER_TO_ODB: git_filter_mode_t = 1;
+
+pub static GIT_FILTER_DEFAULT: u32 = 0;
+
+/* from <git2/sys/filter.h> */
+pub struct git_filter {
+    version: c_uint,
+    attributes: *c_char,
+    initialize: callback_t,
+    shutdown: callback_t,
+    check: callback_t,
+    apply: callback_t,
+    stream: callback_t,
+    cleanup: callback_t,
+}
+
+pub static GIT_FILTER_VERSION: c_uint = 1;
+
+/* from <git2/blob.h> */
+pub static GIT_BLOB_FILTER_OPTIONS_VERSION: c_int = 1;
+
+pub static GIT_BLOB_FILTER_CHECK_FOR_BINARY: u32 = (1 << 0);
+
+pub struct git_blob_filter_options {
+    version: c_int,
+    flags: u32,
+    commit_id: *super::OID,
+    attr_commit_id: super::OID,
+}
+
+/* from <git2/attr.h> */
+pub type git_attr_value_t = c_uint;
+
+pub static GIT_ATTR_VALUE_UNSPECIFIED: git_attr_value_t = 0;
+pub static GIT_ATTR_VALUE_TRUE: git_attr_value_t = 1;
+pub static GIT_ATTR_VALUE_FALSE: git_attr_value_t = 2;
+pub static GIT_ATTR_VALUE_STRING: git_attr_value_t = 3;
+
+/* from <git2/config.h> */
+pub struct git_config_entry {
+    name: *c_char,
+    value: *c_char,
+    include_depth: c_uint,
+    level: c_int,
+    free: callback_t,
+    payload: *c_void,
+}
+
+/* from <git2/stash.h> */
+pub static GIT_STASH_APPLY_OPTIONS_VERSION:c_uint = 1;
+
+pub struct git_stash_apply_options {
+    version: c_uint,
+    flags: u32,
+    checkout_options: git_checkout_options,
+    progress_cb: callback_t,
+    progress_payload: *c_void,
+}
+
+/* from <git2/oidarray.h> */
+pub struct git_oidarray {
+    ids: *super::OID,
+    count: size_t,
 }
 
 /* from <git2/status.h> */
@@ -191,11 +505,23 @@ pub static GIT_BRANCH_REMOTE: git_branch_t = 2;
 // the storage size of these types are unknown
 pub struct git_repository;
 pub struct git_reference;
+pub struct git_annotated_commit;
+pub struct git_config;
+pub struct git_remote;
+pub struct git_refspec;
+pub struct git_config_iterator;
 pub struct git_tree_entry;
 pub struct git_treebuilder;
+pub struct git_filter_list;
+pub struct git_filter_source;
+pub struct git_odb;
+pub struct git_odb_object;
+pub struct git_odb_stream;
 pub struct git_index;
+pub struct git_index_conflict_iterator;
 pub struct git_object;
-pub struct git_diff_list;
+pub struct git_diff;
+pub struct git_credential;
 pub type git_tree = git_object;
 pub type git_commit = git_object;
 pub type git_blob = git_object;
@@ -210,10 +536,12 @@ pub type git_time_t = i64;
 pub type git_time_t = core::libc::types::os::arch::extra::time64_t;
 
 pub type git_off_t = i64;
+pub type git_object_size_t = u64;
 
 pub struct git_time {
     time: git_time_t,
     offset: c_int,
+    sign: c_char,
 }
 
 pub struct git_signature {
@@ -222,17 +550,72 @@ pub struct git_signature {
     when: git_time,
 }
 
+/* from <git2/index.h> */
+pub struct git_index_time {
+    seconds: i32,
+    nanoseconds: u32,
+}
+
+pub struct git_index_entry {
+    ctime: git_index_time,
+    mtime: git_index_time,
+
+    dev: u32,
+    ino: u32,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    file_size: u32,
+
+    id: super::OID,
+
+    flags: u16,
+    flags_extended: u16,
+
+    path: *c_char,
+}
+
+pub struct git_index_reuc_entry {
+    mode: [u32, ..3],
+    oid: [super::OID, ..3],
+    path: *c_char,
+}
+
+/* from <git2/diff.h> */
+pub static GIT_DIFF_OPTIONS_VERSION:c_uint = 1;
+
+pub struct git_diff_file {
+    id: super::OID,
+    path: *c_char,
+    size: git_object_size_t,
+    flags: u32,
+    mode: u16,
+    id_abbrev: u16,
+}
+
+pub struct git_diff_delta {
+    status: super::DiffDelta,
+    flags: u32,
+    similarity: u16,
+    nfiles: u16,
+    old_file: git_diff_file,
+    new_file: git_diff_file,
+}
+
 pub struct git_diff_options {
     version: c_uint,
     flags: u32,
-    context_lines: u16,
-    interhunk_lines: u16,
-    old_prefix: *c_char,
-    new_prefix: *c_char,
+    ignore_submodules: c_int,
     pathspec: git_strarray,
-    max_size: git_off_t,
     notify_cb: callback_t,
-    notify_payload: *c_void,
+    progress_cb: callback_t,
+    payload: *c_void,
+    context_lines: u32,
+    interhunk_lines: u32,
+    id_abbrev: u16,
+    max_size: git_off_t,
+    old_prefix: *c_char,
+    new_prefix: *c_char,
 }
 
 // value type of 'crust' functions is *u8
@@ -241,13 +624,17 @@ pub type callback_t = *u8;
 #[link(name = "git2")]
 extern {
     /* from <git2/errors.h> */
-    pub fn giterr_last() -> *git_error;
+    pub fn git_error_last() -> *git_error;
+    pub fn git_error_set_str(error_class: c_int, string: *c_char) -> c_int;
+
+    /* from <git2/global.h> */
+    pub fn git_libgit2_init() -> c_int;
+    pub fn git_libgit2_shutdown() -> c_int;
 
     /* from <git2/repository.h> */
     pub fn git_repository_open(out: &mut *git_repository, path: *c_char) -> c_int;
     pub fn git_repository_free(repo: *git_repository) -> c_void;
-    pub fn git_repository_discover(path_out: *mut c_char, path_size: size_t,
-                            start_path: *c_char, across_fs: c_int,
+    pub fn git_repository_discover(out: &mut git_buf, start_path: *c_char, across_fs: c_int,
                             ceiling_dirs: *c_char) -> c_int;
     pub fn git_repository_path(repo: *git_repository) -> *c_char;
     pub fn git_repository_workdir(repo: *git_repository) -> *c_char;
@@ -256,6 +643,10 @@ extern {
     pub fn git_repository_is_empty(repo: *git_repository) -> c_int;
     pub fn git_repository_is_bare(repo: *git_repository) -> c_int;
     pub fn git_repository_index(out: &mut *git_index, repo: *git_repository) -> c_int;
+    pub fn git_repository_config(out: &mut *git_config, repo: *git_repository) -> c_int;
+    pub fn git_repository_odb(out: &mut *git_odb, repo: *git_repository) -> c_int;
+    pub fn git_repository_state(repo: *git_repository) -> c_int;
+    pub fn git_repository_state_cleanup(repo: *git_repository) -> c_int;
 
     /* from <git2/refs.h> */
     pub fn git_reference_free(c_ref: *git_reference) -> c_void;
@@ -263,26 +654,53 @@ extern {
                                 name: *c_char) -> c_int;
     pub fn git_reference_resolve(out: &mut *git_reference, c_ref: *git_reference) -> c_int;
     pub fn git_reference_target(c_ref: *git_reference) -> *super::OID;
-
-    /* from <git2/threads.h> */
-    pub fn git_threads_init() -> c_void;
-    pub fn git_threads_shutdown() -> c_void;
+    pub fn git_reference_name(c_ref: *git_reference) -> *c_char;
+    pub fn git_reference_set_target(out: &mut *git_reference, c_ref: *git_reference,
+                                    id: &super::OID, log_message: *c_char) -> c_int;
 
     /* from <git2/clone.h> */
     pub fn git_clone(out: &mut *git_repository, url: *c_char, local_path: *c_char,
                     options: *git_clone_options) -> c_int;
 
     /* from <git2/checkout.h> */
-    pub fn git_checkout_head(repo: *git_repository, opts: *git_checkout_opts) -> c_int;
+    pub fn git_checkout_head(repo: *git_repository, opts: *git_checkout_options) -> c_int;
+    pub fn git_checkout_tree(repo: *git_repository, treeish: *git_object,
+                             opts: &git_checkout_options) -> c_int;
 
     /* from <git2/index.h> */
     pub fn git_index_free(index: *git_index) -> c_void;
     pub fn git_index_write(index: *git_index) -> c_int;
     pub fn git_index_write_tree(out: &mut super::OID, index: *git_index) -> c_int;
+    pub fn git_index_write_tree_to(out: &mut super::OID, index: *git_index,
+        repo: *git_repository) -> c_int;
     pub fn git_index_add_bypath(index: *git_index, path: *c_char) -> c_int;
     pub fn git_index_remove_bypath(index: *git_index, path: *c_char) -> c_int;
     pub fn git_index_read_tree(index: *git_index, tree: *git_tree) -> c_int;
-    pub fn git_index_clear(index: *git_index) -> c_void;
+    pub fn git_index_clear(index: *git_index) -> c_int;
+    pub fn git_index_add_all(index: *git_index, pathspec: &git_strarray, flags: c_uint,
+        callback: callback_t, payload: *c_void) -> c_int;
+    pub fn git_index_remove_all(index: *git_index, pathspec: &git_strarray,
+        callback: callback_t, payload: *c_void) -> c_int;
+    pub fn git_index_update_all(index: *git_index, pathspec: &git_strarray,
+        callback: callback_t, payload: *c_void) -> c_int;
+    pub fn git_index_has_conflicts(index: *git_index) -> c_int;
+    pub fn git_index_conflict_get(ancestor_out: &mut *git_index_entry,
+        our_out: &mut *git_index_entry, their_out: &mut *git_index_entry,
+        index: *git_index, path: *c_char) -> c_int;
+    pub fn git_index_conflict_add(index: *git_index, ancestor_entry: *git_index_entry,
+        our_entry: *git_index_entry, their_entry: *git_index_entry) -> c_int;
+    pub fn git_index_conflict_remove(index: *git_index, path: *c_char) -> c_int;
+    pub fn git_index_conflict_cleanup(index: *git_index) -> c_int;
+    pub fn git_index_conflict_iterator_new(iterator_out: &mut *git_index_conflict_iterator,
+        index: *git_index) -> c_int;
+    pub fn git_index_conflict_next(ancestor_out: &mut *git_index_entry,
+        our_out: &mut *git_index_entry, their_out: &mut *git_index_entry,
+        iterator: *git_index_conflict_iterator) -> c_int;
+    pub fn git_index_conflict_iterator_free(iterator: *git_index_conflict_iterator) -> c_void;
+    pub fn git_index_reuc_entrycount(index: *git_index) -> size_t;
+    pub fn git_index_reuc_get_bypath(index: *git_index, path: *c_char)
+        -> *git_index_reuc_entry;
+    pub fn git_index_reuc_get_byindex(index: *git_index, n: size_t) -> *git_index_reuc_entry;
 
     /* from <git2/status.h> */
     pub fn git_status_foreach(repo: *git_repository, callback: callback_t,
@@ -293,6 +711,8 @@ extern {
 
     /* from <git2/object.h> */
     pub fn git_object_free(object: *git_object) -> c_void;
+    pub fn git_object_dup(out: &mut *git_object, source: *git_object) -> c_int;
+    pub fn git_object_type(obj: *git_object) -> super::OType;
     pub fn git_object_id(obj: *git_object) -> &super::OID;
     pub fn git_object_lookup(out: &mut *git_object, repo: *git_repository, id: &super::OID,
         otype: super::OType) -> c_int;
@@ -315,73 +735,299 @@ extern {
     pub fn git_commit_create(id: &mut super::OID, repo: *git_repository,
         update_ref: *c_char, author: &git_signature, committer: &git_signature,
         message_encoding: *c_char, message: *c_char, tree: *git_tree,
-        parent_count: c_int, parents: **git_commit) -> c_int;
+        parent_count: size_t, parents: **git_commit) -> c_int;
 
     /* from <git2/tree.h> */
     pub fn git_tree_id(tree: *git_tree) -> *super::OID;
     pub fn git_tree_entrycount(tree: *git_tree) -> size_t;
     pub fn git_tree_entry_byname(tree: *git_tree, filename: *c_char) -> *git_tree_entry;
     pub fn git_tree_entry_byindex(tree: *git_tree, idx: size_t) -> *git_tree_entry;
-    pub fn git_tree_entry_byoid(tree: *git_tree, oid: &super::OID) -> *git_tree_entry;
+    pub fn git_tree_entry_byid(tree: *git_tree, id: &super::OID) -> *git_tree_entry;
     pub fn git_tree_entry_bypath(out: &mut *git_tree_entry, tree: *git_tree,
         path: *c_char) -> c_int;
-    pub fn git_tree_entry_dup(entry: *git_tree_entry) -> *git_tree_entry;
+    pub fn git_tree_entry_dup(dest: &mut *git_tree_entry, source: *git_tree_entry) -> c_int;
     pub fn git_tree_entry_free(entry: *git_tree_entry) -> c_void;
     pub fn git_tree_entry_name(entry: *git_tree_entry) -> *c_char;
     pub fn git_tree_entry_id(entry: *git_tree_entry) -> *super::OID;
     pub fn git_tree_entry_type(entry: *git_tree_entry) -> super::OType;
     pub fn git_tree_entry_filemode(entry: *git_tree_entry) -> super::FileMode;
     pub fn git_tree_entry_cmp(e1: *git_tree_entry, e2: *git_tree_entry) -> c_int;
-    pub fn git_treebuilder_create(out: &mut *git_treebuilder, source: *git_tree) -> c_int;
-    pub fn git_treebuilder_clear(bld: *git_treebuilder) -> c_void;
-    pub fn git_treebuilder_entrycount(bld: *git_treebuilder) -> c_uint;
+    pub fn git_tree_entry_to_object(out: &mut *git_object, repo: *git_repository,
+        entry: *git_tree_entry) -> c_int;
+    pub fn git_treebuilder_new(out: &mut *git_treebuilder, repo: *git_repository,
+        source: *git_tree) -> c_int;
+    pub fn git_treebuilder_clear(bld: *git_treebuilder) -> c_int;
+    pub fn git_treebuilder_entrycount(bld: *git_treebuilder) -> size_t;
     pub fn git_treebuilder_free(bld: *git_treebuilder) -> c_void;
     pub fn git_treebuilder_get(bld: *git_treebuilder, filename: *c_char) -> *git_tree_entry;
     pub fn git_treebuilder_insert(out: &mut *git_tree_entry, bld: *git_treebuilder,
         filename: *c_char, id: &super::OID, filemode: super::FileMode) -> c_int;
     pub fn git_treebuilder_remove(bld: *git_treebuilder, filename: *c_char) -> c_int;
     pub fn git_treebuilder_filter(bld: *git_treebuilder, filter: callback_t,
-        payload: *c_void) -> c_void;
-    pub fn git_treebuilder_write(id: &mut super::OID, repo: *git_repository,
-        bld: *git_treebuilder) -> c_int;
+        payload: *c_void) -> c_int;
+    pub fn git_treebuilder_write(id: &mut super::OID, bld: *git_treebuilder) -> c_int;
     pub fn git_tree_walk(tree: *git_tree, mode: git_treewalk_mode, callback: callback_t,
         payload: *c_void) -> c_int;
 
     /* from <git2/blob.h> */
     pub fn git_blob_rawcontent(blob: *git_blob) -> *c_void;
-    pub fn git_blob_rawsize(blob: *git_blob) -> git_off_t;
-    pub fn git_blob_create_fromworkdir(id: &mut super::OID, repo: *git_repository,
+    pub fn git_blob_rawsize(blob: *git_blob) -> git_object_size_t;
+    pub fn git_blob_create_from_workdir(id: &mut super::OID, repo: *git_repository,
         relative_path: *c_char) -> c_int;
-    pub fn git_blob_create_fromdisk(id: &mut super::OID, repo: *git_repository,
+    pub fn git_blob_create_from_disk(id: &mut super::OID, repo: *git_repository,
         path: *c_char) -> c_int;
-    pub fn git_blob_create_fromchunks(id: &mut super::OID, repo: *git_repository,
-        hintpath: *c_char, callback: callback_t, payload: *c_void) -> c_int;
-    pub fn git_blob_create_frombuffer(oid: &mut super::OID, repo: *git_repository,
+    pub fn git_blob_create_from_buffer(oid: &mut super::OID, repo: *git_repository,
         buffer: *c_void, len: size_t) -> c_int;
+    pub fn git_blob_create_from_stream(out: &mut *git_writestream, repo: *git_repository,
+        hintpath: *c_char) -> c_int;
+    pub fn git_blob_create_from_stream_commit(out: &mut super::OID,
+        stream: *git_writestream) -> c_int;
     pub fn git_blob_is_binary(blob: *git_blob) -> c_int;
+    pub fn git_blob_filter(out: &mut git_buf, blob: *git_blob, as_path: *c_char,
+        opts: &git_blob_filter_options) -> c_int;
+
+    /* from <git2/filter.h> */
+    pub fn git_filter_list_load(filters: &mut *git_filter_list, repo: *git_repository,
+        blob: *git_blob, path: *c_char, mode: git_filter_mode_t, flags: u32) -> c_int;
+    pub fn git_filter_list_contains(filters: *git_filter_list, name: *c_char) -> c_int;
+    pub fn git_filter_list_apply_to_buffer(out: &mut git_buf, filters: *git_filter_list,
+        input: *c_char, in_len: size_t) -> c_int;
+    pub fn git_filter_list_apply_to_file(out: &mut git_buf, filters: *git_filter_list,
+        repo: *git_repository, path: *c_char) -> c_int;
+    pub fn git_filter_list_apply_to_blob(out: &mut git_buf, filters: *git_filter_list,
+        blob: *git_blob) -> c_int;
+    pub fn git_filter_list_length(filters: *git_filter_list) -> size_t;
+    pub fn git_filter_list_free(filters: *git_filter_list);
+
+    /* from <git2/sys/filter.h> */
+    pub fn git_filter_register(name: *c_char, filter: *git_filter, priority: c_int) -> c_int;
+    pub fn git_filter_unregister(name: *c_char) -> c_int;
+    pub fn git_filter_source_path(src: *git_filter_source) -> *c_char;
+    pub fn git_filter_source_id(src: *git_filter_source) -> *super::OID;
+    pub fn git_filter_source_mode(src: *git_filter_source) -> git_filter_mode_t;
+
+    /* from <git2/odb.h> */
+    pub fn git_odb_free(db: *git_odb);
+    pub fn git_odb_exists(db: *git_odb, id: &super::OID) -> c_int;
+    pub fn git_odb_read_header(len_out: &mut size_t, type_out: &mut super::OType, db: *git_odb,
+        id: &super::OID) -> c_int;
+    pub fn git_odb_read(out: &mut *git_odb_object, db: *git_odb, id: &super::OID) -> c_int;
+    pub fn git_odb_write(out: &mut super::OID, db: *git_odb, data: *c_void, len: size_t,
+        otype: super::OType) -> c_int;
+    pub fn git_odb_foreach(db: *git_odb, cb: callback_t, payload: *c_void) -> c_int;
+    pub fn git_odb_open_wstream(out: &mut *git_odb_stream, db: *git_odb,
+        size: git_object_size_t, otype: super::OType) -> c_int;
+    pub fn git_odb_open_rstream(out: &mut *git_odb_stream, len: &mut size_t,
+        otype: &mut super::OType, db: *git_odb, id: &super::OID) -> c_int;
+    pub fn git_odb_stream_write(stream: *git_odb_stream, buffer: *c_char, len: size_t) -> c_int;
+    pub fn git_odb_stream_finalize_write(out: &mut super::OID, stream: *git_odb_stream) -> c_int;
+    pub fn git_odb_stream_read(stream: *git_odb_stream, buffer: *mut c_char, len: size_t) -> c_int;
+    pub fn git_odb_stream_free(stream: *git_odb_stream);
+    pub fn git_odb_object_free(object: *git_odb_object);
+    pub fn git_odb_object_data(object: *git_odb_object) -> *c_void;
+    pub fn git_odb_object_size(object: *git_odb_object) -> size_t;
+    pub fn git_odb_object_type(object: *git_odb_object) -> super::OType;
+
+    /* from <git2/attr.h> */
+    pub fn git_attr_value(attr: *c_char) -> git_attr_value_t;
 
     /* from <git2/branch.h> */
     pub fn git_branch_create(out: &mut *git_reference, repo: *git_repository,
         branch_name: *c_char, target: *git_commit, force: c_int) -> c_int;
     pub fn git_branch_delete(branch: *git_reference) -> c_int;
-    pub fn git_branch_foreach(repo: *git_repository, list_flags: c_uint, branch_cb: callback_t,
-        payload: *c_void) -> c_int;
     pub fn git_branch_move(out: &mut *git_reference, branch: *git_reference,
         new_branch_name: *c_char, force: c_int) -> c_int;
     pub fn git_branch_lookup(out: &mut *git_reference, repo: *git_repository, 
         branch_name: *c_char, branch_type: git_branch_t) -> c_int;
     pub fn git_branch_upstream(out: &mut *git_reference, branch: *git_reference) -> c_int;
     pub fn git_branch_set_upstream(branch: *git_reference, upstream_name: *c_char) -> c_int;
-    pub fn git_branch_upstream_name(tracking_branch_name_out: *mut c_char, buffer_size: size_t,
-        repo: *git_repository, canonical_branch_name: *c_char) -> c_int;
+    pub fn git_branch_upstream_name(out: &mut git_buf, repo: *git_repository,
+        refname: *c_char) -> c_int;
     pub fn git_branch_is_head(branch: *git_reference) -> c_int;
-    pub fn git_branch_remote_name(remote_name_out: *mut c_char, buffer_size: size_t, 
-        repo: *git_repository, canonical_branch_name: *c_char) -> c_int;
+    pub fn git_branch_remote_name(out: &mut git_buf, repo: *git_repository,
+        refname: *c_char) -> c_int;
+
+    /* from <git2/oidarray.h> */
+    pub fn git_oidarray_dispose(array: &mut git_oidarray) -> c_void;
+
+    /* from <git2/merge.h> */
+    pub fn git_merge_base(out: &mut super::OID, repo: *git_repository, one: &super::OID,
+        two: &super::OID) -> c_int;
+    pub fn git_merge_bases_many(out: &mut git_oidarray, repo: *git_repository, length: size_t,
+        input_array: *super::OID) -> c_int;
+    pub fn git_merge_base_octopus(out: &mut super::OID, repo: *git_repository,
+        length: size_t, input_array: *super::OID) -> c_int;
+    pub fn git_merge_analysis(analysis_out: &mut c_uint, preference_out: &mut c_uint,
+        repo: *git_repository, their_heads: **git_annotated_commit,
+        their_heads_len: size_t) -> c_int;
+    pub fn git_merge(repo: *git_repository, their_heads: **git_annotated_commit,
+        their_heads_len: size_t, merge_opts: &git_merge_options,
+        checkout_opts: &git_checkout_options) -> c_int;
+    pub fn git_merge_trees(out: &mut *git_index, repo: *git_repository,
+        ancestor_tree: *git_tree, our_tree: *git_tree, their_tree: *git_tree,
+        opts: &git_merge_options) -> c_int;
+    pub fn git_merge_commits(out: &mut *git_index, repo: *git_repository,
+        our_commit: *git_commit, their_commit: *git_commit,
+        opts: &git_merge_options) -> c_int;
+
+    /* from <git2/buffer.h> */
+    pub fn git_buf_dispose(buffer: &mut git_buf) -> c_void;
+    pub fn git_buf_set(buffer: &mut git_buf, data: *c_void, datalen: size_t) -> c_int;
+
+    /* from <git2/config.h> */
+    pub fn git_config_find_global(out: &mut git_buf) -> c_int;
+    pub fn git_config_find_xdg(out: &mut git_buf) -> c_int;
+    pub fn git_config_find_system(out: &mut git_buf) -> c_int;
+    pub fn git_config_open_default(out: &mut *git_config) -> c_int;
+    pub fn git_config_new(out: &mut *git_config) -> c_int;
+    pub fn git_config_open_ondisk(out: &mut *git_config, path: *c_char) -> c_int;
+    pub fn git_config_snapshot(out: &mut *git_config, config: *git_config) -> c_int;
+    pub fn git_config_add_file_ondisk(cfg: *git_config, path: *c_char, level: c_int,
+        repo: *git_repository, force: c_int) -> c_int;
+    pub fn git_config_open_level(out: &mut *git_config, parent: *git_config,
+        level: c_int) -> c_int;
+    pub fn git_config_free(cfg: *git_config) -> c_void;
+    pub fn git_config_get_int32(out: &mut i32, cfg: *git_config, name: *c_char) -> c_int;
+    pub fn git_config_get_int64(out: &mut i64, cfg: *git_config, name: *c_char) -> c_int;
+    pub fn git_config_get_bool(out: &mut c_int, cfg: *git_config, name: *c_char) -> c_int;
+    pub fn git_config_get_string(out: &mut *c_char, cfg: *git_config, name: *c_char) -> c_int;
+    pub fn git_config_get_string_buf(out: &mut git_buf, cfg: *git_config,
+        name: *c_char) -> c_int;
+    pub fn git_config_get_path(out: &mut git_buf, cfg: *git_config, name: *c_char) -> c_int;
+    pub fn git_config_get_multivar_foreach(cfg: *git_config, name: *c_char, regexp: *c_char,
+        callback: callback_t, payload: *c_void) -> c_int;
+    pub fn git_config_set_int32(cfg: *git_config, name: *c_char, value: i32) -> c_int;
+    pub fn git_config_set_int64(cfg: *git_config, name: *c_char, value: i64) -> c_int;
+    pub fn git_config_set_bool(cfg: *git_config, name: *c_char, value: c_int) -> c_int;
+    pub fn git_config_set_string(cfg: *git_config, name: *c_char, value: *c_char) -> c_int;
+    pub fn git_config_set_multivar(cfg: *git_config, name: *c_char, regexp: *c_char,
+        value: *c_char) -> c_int;
+    pub fn git_config_delete_entry(cfg: *git_config, name: *c_char) -> c_int;
+    pub fn git_config_delete_multivar(cfg: *git_config, name: *c_char,
+        regexp: *c_char) -> c_int;
+    pub fn git_config_iterator_new(out: &mut *git_config_iterator, cfg: *git_config) -> c_int;
+    pub fn git_config_iterator_glob_new(out: &mut *git_config_iterator, cfg: *git_config,
+        regexp: *c_char) -> c_int;
+    pub fn git_config_next(entry: &mut *git_config_entry,
+        iter: *git_config_iterator) -> c_int;
+    pub fn git_config_iterator_free(iter: *git_config_iterator) -> c_void;
+    pub fn git_config_parse_bool(out: &mut c_int, value: *c_char) -> c_int;
+    pub fn git_config_parse_int32(out: &mut i32, value: *c_char) -> c_int;
+    pub fn git_config_parse_int64(out: &mut i64, value: *c_char) -> c_int;
+    pub fn git_config_parse_path(out: &mut git_buf, value: *c_char) -> c_int;
+
+    /* from <git2/strarray.h> */
+    pub fn git_strarray_dispose(array: &mut git_strarray) -> c_void;
+
+    /* from <git2/remote.h> */
+    pub fn git_remote_list(out: &mut git_strarray, repo: *git_repository) -> c_int;
+    pub fn git_remote_lookup(out: &mut *git_remote, repo: *git_repository,
+        name: *c_char) -> c_int;
+    pub fn git_remote_create(out: &mut *git_remote, repo: *git_repository, name: *c_char,
+        url: *c_char) -> c_int;
+    pub fn git_remote_create_anonymous(out: &mut *git_remote, repo: *git_repository,
+        url: *c_char) -> c_int;
+    pub fn git_remote_rename(problems: &mut git_strarray, repo: *git_repository,
+        name: *c_char, new_name: *c_char) -> c_int;
+    pub fn git_remote_delete(repo: *git_repository, name: *c_char) -> c_int;
+    pub fn git_remote_free(remote: *git_remote) -> c_void;
+    pub fn git_remote_owner(remote: *git_remote) -> *git_repository;
+    pub fn git_remote_name(remote: *git_remote) -> *c_char;
+    pub fn git_remote_url(remote: *git_remote) -> *c_char;
+    pub fn git_remote_pushurl(remote: *git_remote) -> *c_char;
+    pub fn git_remote_set_url(repo: *git_repository, remote: *c_char, url: *c_char) -> c_int;
+    pub fn git_remote_set_pushurl(repo: *git_repository, remote: *c_char,
+        url: *c_char) -> c_int;
+    pub fn git_remote_get_fetch_refspecs(array: &mut git_strarray,
+        remote: *git_remote) -> c_int;
+    pub fn git_remote_get_push_refspecs(array: &mut git_strarray,
+        remote: *git_remote) -> c_int;
+    pub fn git_remote_add_fetch(repo: *git_repository, remote: *c_char,
+        refspec: *c_char) -> c_int;
+    pub fn git_remote_add_push(repo: *git_repository, remote: *c_char,
+        refspec: *c_char) -> c_int;
+    pub fn git_remote_autotag(remote: *git_remote) -> git_remote_autotag_option_t;
+    pub fn git_remote_set_autotag(repo: *git_repository, remote: *c_char,
+        value: git_remote_autotag_option_t) -> c_int;
+    pub fn git_remote_fetch(remote: *git_remote, refspecs: &git_strarray,
+        opts: &git_fetch_options, reflog_message: *c_char) -> c_int;
+    pub fn git_remote_push(remote: *git_remote, refspecs: &git_strarray,
+        opts: &git_push_options) -> c_int;
+    pub fn git_remote_stats(remote: *git_remote) -> *git_indexer_progress;
+    pub fn git_remote_connect(remote: *git_remote, direction: git_direction,
+        callbacks: *git_remote_callbacks, proxy_opts: *git_proxy_options,
+        custom_headers: *git_strarray) -> c_int;
+    pub fn git_remote_connected(remote: *git_remote) -> c_int;
+    pub fn git_remote_disconnect(remote: *git_remote) -> c_int;
+    pub fn git_remote_ls(out: &mut **git_remote_head, size: &mut size_t,
+        remote: *git_remote) -> c_int;
+    pub fn git_remote_default_branch(out: &mut git_buf, remote: *git_remote) -> c_int;
+    pub fn git_remote_refspec_count(remote: *git_remote) -> size_t;
+    pub fn git_remote_get_refspec(remote: *git_remote, n: size_t) -> *git_refspec;
+
+    /* from <git2/credential.h> */
+    pub fn git_credential_userpass_plaintext_new(out: &mut *git_credential, username: *c_char,
+        password: *c_char) -> c_int;
+    pub fn git_credential_ssh_key_new(out: &mut *git_credential, username: *c_char,
+        publickey: *c_char, privatekey: *c_char, passphrase: *c_char) -> c_int;
+    pub fn git_credential_ssh_key_memory_new(out: &mut *git_credential, username: *c_char,
+        publickey: *c_char, privatekey: *c_char, passphrase: *c_char) -> c_int;
+    pub fn git_credential_ssh_key_from_agent(out: &mut *git_credential,
+        username: *c_char) -> c_int;
+    pub fn git_credential_default_new(out: &mut *git_credential) -> c_int;
+    pub fn git_credential_username_new(out: &mut *git_credential, username: *c_char) -> c_int;
+
+    /* from <git2/sys/transport.h> */
+    pub fn git_transport_register(scheme: *c_char, cb: callback_t, param: *c_void) -> c_int;
+    pub fn git_transport_unregister(scheme: *c_char) -> c_int;
+    pub fn git_transport_smart(out: &mut *git_transport, owner: *git_remote,
+        payload: *c_void) -> c_int;
+
+    /* from <git2/refspec.h> */
+    pub fn git_refspec_direction(spec: *git_refspec) -> git_direction;
+    pub fn git_refspec_src_matches(spec: *git_refspec, refname: *c_char) -> c_int;
+    pub fn git_refspec_transform(out: &mut git_buf, spec: *git_refspec,
+        name: *c_char) -> c_int;
+
+    /* from <git2/signature.h> */
+    pub fn git_signature_now(out: &mut *git_signature, name: *c_char,
+        email: *c_char) -> c_int;
+    pub fn git_signature_default(out: &mut *git_signature, repo: *git_repository) -> c_int;
+    pub fn git_signature_free(sig: *git_signature) -> c_void;
+
+    /* from <git2/stash.h> */
+    pub fn git_stash_save(out: &mut super::OID, repo: *git_repository,
+        stasher: &git_signature, message: *c_char, flags: c_uint) -> c_int;
+    pub fn git_stash_foreach(repo: *git_repository, callback: callback_t,
+        payload: *c_void) -> c_int;
+    pub fn git_stash_apply(repo: *git_repository, index: size_t,
+        options: &git_stash_apply_options) -> c_int;
+    pub fn git_stash_pop(repo: *git_repository, index: size_t,
+        options: &git_stash_apply_options) -> c_int;
+    pub fn git_stash_drop(repo: *git_repository, index: size_t) -> c_int;
+
+    /* from <git2/annotated_commit.h> */
+    pub fn git_annotated_commit_lookup(out: &mut *git_annotated_commit,
+        repo: *git_repository, id: &super::OID) -> c_int;
+    pub fn git_annotated_commit_from_ref(out: &mut *git_annotated_commit,
+        repo: *git_repository, c_ref: *git_reference) -> c_int;
+    pub fn git_annotated_commit_id(commit: *git_annotated_commit) -> *super::OID;
+    pub fn git_annotated_commit_free(commit: *git_annotated_commit) -> c_void;
+
+    /* from <git2/graph.h> */
+    pub fn git_graph_ahead_behind(ahead: &mut size_t, behind: &mut size_t,
+        repo: *git_repository, local: &super::OID, upstream: &super::OID) -> c_int;
+    pub fn git_graph_descendant_of(repo: *git_repository, commit: &super::OID,
+        ancestor: &super::OID) -> c_int;
 
     /* from <git2/diff.h> */
-    pub fn git_diff_list_free(diff: *git_diff_list) -> c_void;
-    pub fn git_diff_tree_to_tree(diff: &mut *git_diff_list, repo: *git_repository,
+    pub fn git_diff_free(diff: *git_diff) -> c_void;
+    pub fn git_diff_tree_to_tree(diff: &mut *git_diff, repo: *git_repository,
         old_tree: *git_tree, new_tree: *git_tree, opts: *git_diff_options) -> c_int;
+    pub fn git_diff_index_to_workdir(diff: &mut *git_diff, repo: *git_repository,
+        index: *git_index, opts: *git_diff_options) -> c_int;
+    pub fn git_diff_num_deltas(diff: *git_diff) -> size_t;
+    pub fn git_diff_get_delta(diff: *git_diff, idx: size_t) -> *git_diff_delta;
 }
 
 /* from <git2/commit.h> */
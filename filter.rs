@@ -0,0 +1,314 @@
+use std::{ptr, vec, str, cast, c_str};
+use std::libc::{c_char, c_int, c_void, size_t};
+use ext;
+use super::{OID, GitError, last_error, set_error};
+use super::Repository;
+use super::blob;
+use super::blob::Blob;
+
+/// Direction in which content is filtered
+pub enum FilterMode {
+    /** From the object database to the working directory, as in a checkout (smudge) */
+    GIT_FILTER_TO_WORKTREE = 0,
+    /** From the working directory to the object database, as in an add (clean) */
+    GIT_FILTER_TO_ODB = 1,
+}
+
+pub unsafe fn from_c_buf_bytes(buf: &mut ext::git_buf) -> ~[u8] {
+    let v = vec::raw::from_buf_raw(buf.ptr as *u8, buf.size as uint);
+    ext::git_buf_dispose(buf);
+    v
+}
+
+/// The filters that apply to a path, in one direction
+///
+/// The built-in filters are `crlf`, driven by the `text` and `eol` attributes
+/// and `core.autocrlf`, and `ident`, driven by the `ident` attribute.
+/// A list may be empty, in which case content goes through unchanged.
+pub struct FilterList<'r> {
+    priv filters: *ext::git_filter_list,
+    priv owner: &'r Repository,
+}
+
+impl<'r> FilterList<'r> {
+    /// Load the filters to apply to the content of `path`
+    ///
+    /// path: the path of the file, relative to the working directory.
+    ///  The file does not need to exist; only its name is used to look up attributes.
+    /// blob: the blob the content comes from, if any; some filters use its id.
+    pub fn load(repo: &'r Repository, blob: Option<&Blob>, path: &str, mode: FilterMode)
+        -> Result<FilterList<'r>, (~str, GitError)>
+    {
+        let mut filters: *ext::git_filter_list = ptr::null();
+        let c_blob = match blob {
+            Some(b) => blob::raw_blob(b),
+            None => ptr::null(),
+        };
+        path.with_c_str(|c_path| {
+            unsafe {
+                if ext::git_filter_list_load(&mut filters, repo.repo, c_blob, c_path,
+                                             mode as ext::git_filter_mode_t,
+                                             ext::GIT_FILTER_DEFAULT) == 0 {
+                    Ok( FilterList { filters: filters, owner: repo } )
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Number of filters in the list
+    pub fn len(&self) -> uint
+    {
+        unsafe {
+            ext::git_filter_list_length(self.filters) as uint
+        }
+    }
+
+    /// Whether the filter named `name`, e.g. "crlf" or "ident", is in the list
+    pub fn contains(&self, name: &str) -> bool
+    {
+        name.with_c_str(|c_name| {
+            unsafe {
+                ext::git_filter_list_contains(self.filters, c_name) != 0
+            }
+        })
+    }
+
+    /// Apply the filters to a buffer
+    pub fn apply_to_data(&self, data: &[u8]) -> Result<~[u8], (~str, GitError)>
+    {
+        let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
+        unsafe {
+            if ext::git_filter_list_apply_to_buffer(&mut buf, self.filters,
+                                                    data.as_ptr() as *c_char,
+                                                    data.len() as size_t) == 0 {
+                Ok( from_c_buf_bytes(&mut buf) )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Apply the filters to the content of a file
+    ///
+    /// path: the path of the file, relative to the working directory
+    pub fn apply_to_file(&self, path: &str) -> Result<~[u8], (~str, GitError)>
+    {
+        let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
+        path.with_c_str(|c_path| {
+            unsafe {
+                if ext::git_filter_list_apply_to_file(&mut buf, self.filters, self.owner.repo,
+                                                      c_path) == 0 {
+                    Ok( from_c_buf_bytes(&mut buf) )
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Apply the filters to the content of a blob
+    pub fn apply_to_blob(&self, blob: &Blob) -> Result<~[u8], (~str, GitError)>
+    {
+        let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
+        unsafe {
+            let c_blob = blob::raw_blob(blob);
+            if ext::git_filter_list_apply_to_blob(&mut buf, self.filters, c_blob) == 0 {
+                Ok( from_c_buf_bytes(&mut buf) )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<'r> Drop for FilterList<'r> {
+    fn drop(&mut self) {
+        unsafe {
+            ext::git_filter_list_free(self.filters);
+        }
+    }
+}
+
+/** Priority of the built-in CRLF filter */
+pub static GIT_FILTER_CRLF_PRIORITY: int = 0;
+/** Priority of the built-in ident filter */
+pub static GIT_FILTER_IDENT_PRIORITY: int = 100;
+/** Priority of the filters defined by `filter.<name>` drivers */
+pub static GIT_FILTER_DRIVER_PRIORITY: int = 200;
+
+/// The file a filter is run on
+pub struct FilterSource {
+    /// Path of the file, relative to the working directory
+    path: ~str,
+    mode: FilterMode,
+    /// Id of the blob being filtered, when known
+    id: Option<OID>,
+}
+
+unsafe fn from_c_filter_source(src: *ext::git_filter_source) -> FilterSource {
+    let id = ext::git_filter_source_id(src);
+    FilterSource {
+        path: str::raw::from_c_str(ext::git_filter_source_path(src)),
+        mode: match ext::git_filter_source_mode(src) {
+            ext::GIT_FILTER_TO_WORKTREE => GIT_FILTER_TO_WORKTREE,
+            _ => GIT_FILTER_TO_ODB,
+        },
+        id: if id.is_null() { None } else { Some(*id) },
+    }
+}
+
+/// A content filter implemented in Rust, like a `filter.<name>.clean` and
+/// `.smudge` driver without the external commands.
+///
+/// Register it with `filter_register`. libgit2 then runs it wherever it
+/// filters content: checkout, adding to the index, creating blobs from the
+/// working directory, diff, and `FilterList`.
+pub trait Filter {
+    /// Decide whether to filter the file described by `src`
+    ///
+    /// attr_values: the values of the attributes the filter was registered
+    ///  with, in the same order. None if the attribute is unspecified;
+    ///  set and unset attributes give "true" and "false".
+    fn check(&mut self, _src: &FilterSource, _attr_values: &[Option<~str>]) -> bool {
+        true
+    }
+
+    /// Filter `input`, in the direction given by `src.mode`.
+    /// Returns None to leave the content unchanged.
+    fn apply(&mut self, src: &FilterSource, input: &[u8])
+        -> Result<Option<~[u8]>, (~str, GitError)>;
+
+    /// Called when libgit2 is done with a file `check` accepted
+    fn cleanup(&mut self, _src: &FilterSource) {}
+}
+
+// libgit2 hands back the vtable pointer, so it must come first
+struct RawFilter {
+    parent: ext::git_filter,
+    filter: ~Filter,
+    attributes: c_str::CString,
+    nattrs: uint,
+}
+
+extern "C" fn check_cb(filter: *ext::git_filter, payload: &mut *c_void,
+                       src: *ext::git_filter_source, attr_values: **c_char) -> c_int
+{
+    unsafe {
+        let raw: &mut RawFilter = cast::transmute(filter);
+        let src = from_c_filter_source(src);
+        let values: ~[Option<~str>] = range(0, raw.nattrs).map(|i| {
+            let value = *ptr::offset(attr_values, i as int);
+            match ext::git_attr_value(value) {
+                ext::GIT_ATTR_VALUE_TRUE => Some(~"true"),
+                ext::GIT_ATTR_VALUE_FALSE => Some(~"false"),
+                ext::GIT_ATTR_VALUE_STRING => Some(str::raw::from_c_str(value)),
+                _ => None,
+            }
+        }).collect();
+        if raw.filter.check(&src, values) {
+            // kept for cleanup
+            *payload = cast::transmute(~src);
+            0
+        } else {
+            ext::GIT_PASSTHROUGH
+        }
+    }
+}
+
+extern "C" fn apply_cb(filter: *ext::git_filter, _payload: &mut *c_void, to: &mut ext::git_buf,
+                       from: &ext::git_buf, src: *ext::git_filter_source) -> c_int
+{
+    unsafe {
+        let raw: &mut RawFilter = cast::transmute(filter);
+        let src = from_c_filter_source(src);
+        let res = vec::raw::buf_as_slice(from.ptr as *u8, from.size as uint, |input| {
+            raw.filter.apply(&src, input)
+        });
+        match res {
+            Ok(Some(output)) => ext::git_buf_set(to, output.as_ptr() as *c_void,
+                                                 output.len() as size_t),
+            Ok(None) => ext::GIT_PASSTHROUGH,
+            Err(e) => set_error(e),
+        }
+    }
+}
+
+extern "C" fn cleanup_cb(filter: *ext::git_filter, payload: *c_void)
+{
+    unsafe {
+        if !payload.is_null() {
+            let raw: &mut RawFilter = cast::transmute(filter);
+            let src: ~FilterSource = cast::transmute(payload);
+            raw.filter.cleanup(&*src);
+        }
+    }
+}
+
+extern "C" fn shutdown_cb(filter: *ext::git_filter)
+{
+    unsafe {
+        let _raw: ~RawFilter = cast::transmute(filter);
+    }
+}
+
+/// Run `filter` on the files whose attributes match `attributes`, e.g.
+/// "filter=ourlfs" for the files with `filter=ourlfs` in `.gitattributes`.
+///
+/// attributes: whitespace-separated attribute names, each optionally with the
+///  value it must have, e.g. "text eol=lf". A name prefixed with `-` or `!`
+///  requires the attribute to be unset or unspecified.
+/// priority: filters with a lower priority run first when cleaning, last when
+///  smudging. See `GIT_FILTER_DRIVER_PRIORITY` and the built-in priorities.
+///
+/// Registrations are global to the process. libgit2 drops the filter once it
+/// is unregistered, or when libgit2 shuts down, if it has been used.
+pub fn filter_register(name: &str, attributes: &str, priority: int, filter: ~Filter)
+    -> Result<(), (~str, GitError)>
+{
+    super::global_init();
+    unsafe {
+        let attributes = attributes.to_c_str();
+        let raw = ~RawFilter {
+            parent: ext::git_filter {
+                version: ext::GIT_FILTER_VERSION,
+                attributes: attributes.with_ref(|p| p),
+                initialize: ptr::null(),
+                shutdown: cast::transmute(shutdown_cb),
+                check: cast::transmute(check_cb),
+                apply: cast::transmute(apply_cb),
+                stream: ptr::null(),
+                cleanup: cast::transmute(cleanup_cb),
+            },
+            filter: filter,
+            nattrs: attributes.as_str().unwrap().words().count(),
+            attributes: attributes,
+        };
+        let c_filter: *ext::git_filter = cast::transmute(raw);
+        name.with_c_str(|c_name| {
+            if ext::git_filter_register(c_name, c_filter, priority as c_int) == 0 {
+                Ok(())
+            } else {
+                let _raw: ~RawFilter = cast::transmute(c_filter);
+                Err( last_error() )
+            }
+        })
+    }
+}
+
+/// Remove a filter registered with `filter_register`
+pub fn filter_unregister(name: &str) -> Result<(), (~str, GitError)>
+{
+    super::global_init();
+    name.with_c_str(|c_name| {
+        unsafe {
+            if ext::git_filter_unregister(c_name) == 0 {
+                Ok(())
+            } else {
+                Err( last_error() )
+            }
+        }
+    })
+}
@@ -3,14 +3,16 @@
 
 #[crate_type = "lib"];
 
-use std::{ptr, vec, str, cast};
-use std::libc::{c_uint, c_char, c_int, c_void};
+use std::{ptr, vec, str, cast, c_str, io};
+use std::libc::{c_uint, c_char, c_int, c_void, size_t};
 
 pub use reference::Reference;
 pub use commit::Commit;
 pub use tree::Tree;
 pub use git_index::GitIndex;
 pub use blob::Blob;
+pub use config::Config;
+pub use remote::Remote;
 
 pub mod ext;
 pub mod reference;
@@ -21,6 +23,15 @@ pub mod commit;
 pub mod signature;
 pub mod oid;
 pub mod diff;
+pub mod merge;
+pub mod checkout;
+pub mod stash;
+pub mod config;
+pub mod remote;
+pub mod cred;
+pub mod transport;
+pub mod filter;
+pub mod odb;
 
 condition! {
     git_error: (~str, super::GitError) -> ();
@@ -31,14 +42,34 @@ pub unsafe fn raise() {
 }
 
 pub unsafe fn last_error() -> (~str, GitError) {
-    let err = ext::giterr_last();
+    let err = ext::git_error_last();
+    if err.is_null() {
+        return (~"no error", GITERR_NONE);
+    }
     let message = std::str::raw::from_c_str((*err).message);
     let klass = (*err).klass;
     (message, klass)
 }
 
+pub unsafe fn from_c_buf(buf: &mut ext::git_buf) -> ~str {
+    let s = str::raw::from_buf_len(buf.ptr as *u8, buf.size as uint);
+    ext::git_buf_dispose(buf);
+    s
+}
+
+// report an error from a callback to libgit2, which hands it back through last_error
+unsafe fn set_error(err: (~str, GitError)) -> c_int {
+    let (message, klass) = err;
+    message.with_c_str(|c_message| {
+        ext::git_error_set_str(klass as c_int, c_message);
+    });
+    -1
+}
+
 /** Error classes */
+#[deriving(Eq)]
 pub enum GitError {
+    GITERR_NONE,
     GITERR_NOMEMORY,
     GITERR_OS,
     GITERR_INVALID,
@@ -61,6 +92,19 @@ pub enum GitError {
     GITERR_CHECKOUT,
     GITERR_FETCHHEAD,
     GITERR_MERGE,
+    GITERR_SSH,
+    GITERR_FILTER,
+    GITERR_REVERT,
+    GITERR_CALLBACK,
+    GITERR_CHERRYPICK,
+    GITERR_DESCRIBE,
+    GITERR_REBASE,
+    GITERR_FILESYSTEM,
+    GITERR_PATCH,
+    GITERR_WORKTREE,
+    GITERR_SHA1,
+    GITERR_HTTP,
+    GITERR_INTERNAL,
 }
 
 pub enum WalkMode {
@@ -82,7 +126,7 @@ pub enum DiffDelta {
 }
 
 pub struct DiffList {
-    priv difflist: *ext::git_diff_list,
+    priv difflist: *ext::git_diff,
 }
 
 pub struct Time {
@@ -109,6 +153,27 @@ fn with_opt_c_str<T>(s: Option<&str>, f: |*c_char| -> T) -> T
     }
 }
 
+fn with_c_strarray<T>(strs: &[&str], f: |&ext::git_strarray| -> T) -> T
+{
+    let c_strs: ~[c_str::CString] = strs.iter().map(|s| s.to_c_str()).collect();
+    let ptrs: ~[*c_char] = c_strs.iter().map(|c| c.with_ref(|p| p)).collect();
+    let arr = ext::git_strarray {
+        strings: ptrs.as_ptr(),
+        count: ptrs.len() as size_t,
+    };
+    f(&arr)
+}
+
+// copies the strings of a strarray allocated by libgit2, then frees it
+unsafe fn from_c_strarray(arr: &mut ext::git_strarray) -> ~[~str]
+{
+    let strs = vec::raw::buf_as_slice(arr.strings, arr.count as uint, |ptrs| {
+        ptrs.map(|&p| str::raw::from_c_str(p))
+    });
+    ext::git_strarray_dispose(arr);
+    strs
+}
+
 /// Status flags for a single file.
 ///
 /// A combination of these values will be returned to indicate the status of a file.
@@ -151,6 +216,21 @@ impl Status {
     }
 }
 
+/// Ongoing operation of a repository, as left in the `.git` directory
+#[deriving(Eq)]
+pub enum RepositoryState {
+    GIT_REPOSITORY_STATE_NONE,
+    GIT_REPOSITORY_STATE_MERGE,
+    GIT_REPOSITORY_STATE_REVERT,
+    GIT_REPOSITORY_STATE_CHERRY_PICK,
+    GIT_REPOSITORY_STATE_BISECT,
+    GIT_REPOSITORY_STATE_REBASE,
+    GIT_REPOSITORY_STATE_REBASE_INTERACTIVE,
+    GIT_REPOSITORY_STATE_REBASE_MERGE,
+    GIT_REPOSITORY_STATE_APPLY_MAILBOX,
+    GIT_REPOSITORY_STATE_APPLY_MAILBOX_OR_REBASE,
+}
+
 /// Valid modes for index and tree entries.
 pub enum FileMode {
     GIT_FILEMODE_NEW                    = 0x0000,   // 0000000
@@ -176,13 +256,20 @@ pub enum OType {
     GIT_OBJ_REF_DELTA = 7,  // A delta, base is given by object id.
 }
 
+/// An object loaded from the repository whose type was not known in advance
+pub enum Object<'r> {
+    ObjCommit(~Commit<'r>),
+    ObjTree(~Tree<'r>),
+    ObjBlob(~Blob<'r>),
+}
+
 
 // FIXME: there should be better ways to do this...
 // if you call this library in multiple tasks,
 // this function must be called before calling any other functions in library
 pub fn threads_init() {
     unsafe {
-        ext::git_threads_init();
+        ext::git_libgit2_init();
     }
 }
 
@@ -190,14 +277,31 @@ pub fn threads_init() {
 // this function must be called before shutting down the library
 pub fn threads_shutdown() {
     unsafe {
-        ext::git_threads_shutdown();
+        ext::git_libgit2_shutdown();
     }
 }
 
-static PATH_BUF_SZ: uint = 1024u;
+static mut INITIALIZED: bool = false;
+
+// libgit2 must be initialized before any other call. The entry points of
+// this library do it on first use, and the reference is never released.
+// Initializing twice in a race is harmless: libgit2 counts the references.
+fn global_init() {
+    unsafe {
+        if !INITIALIZED {
+            ext::git_libgit2_init();
+            INITIALIZED = true;
+        }
+    }
+}
+
+// size of the chunks blob_create_fromchunks asks for
+static BLOB_CHUNK_SZ: uint = 8192u;
 
 pub struct Repository {
     priv repo: *ext::git_repository,
+    // false if libgit2 owns the repository, as for the one handed to a transport
+    priv owned: bool,
 }
 
 impl Repository {
@@ -209,6 +313,7 @@ impl Repository {
     /// or bare repository or raise bad_repo if 'path' is neither.
     pub fn open(path: &str) -> Result<Repository, (~str, GitError)>
     {
+        global_init();
         unsafe {
             let mut ptr_to_repo: *ext::git_repository = ptr::null();
             path.with_c_str({ |c_path|
@@ -228,6 +333,7 @@ impl Repository {
     /// will be created.
     pub fn init(path: &str, is_bare: bool) -> Result<Repository, (~str, GitError)>
     {
+        global_init();
         unsafe {
             let mut ptr_to_repo: *ext::git_repository = ptr::null();
             path.with_c_str(|c_path| {
@@ -256,16 +362,15 @@ impl Repository {
     /// might be empty string
     pub fn discover(start_path: &str, across_fs: bool, ceiling_dirs: &str) -> Option<~str>
     {
+        global_init();
         unsafe {
-            let mut buf = vec::from_elem(PATH_BUF_SZ, 0u8 as c_char);
-            let c_path = buf.as_mut_ptr();
+            let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
             start_path.with_c_str(|c_start_path| {
                 ceiling_dirs.with_c_str(|c_ceiling_dirs| {
-                    let result = ext::git_repository_discover(c_path, PATH_BUF_SZ as u64,
-                                            c_start_path, across_fs as c_int, c_ceiling_dirs);
-                    let imm_path = c_path as *c_char;
+                    let result = ext::git_repository_discover(&mut buf, c_start_path,
+                                            across_fs as c_int, c_ceiling_dirs);
                     if result == 0 {
-                        Some( str::raw::from_c_str(imm_path) )
+                        Some( from_c_buf(&mut buf) )
                     } else {
                         None
                     }
@@ -277,6 +382,7 @@ impl Repository {
     /// Clone a remote repository, and checkout the branch pointed to by the remote
     /// this function do not receive options for now
     pub fn clone(url: &str, local_path: &str) -> Result<Repository, (~str, GitError)> {
+        global_init();
         unsafe {
             let mut ptr_to_repo: *ext::git_repository = ptr::null();
             url.with_c_str(|c_url| {
@@ -291,8 +397,52 @@ impl Repository {
         }
     }
 
+    /// Clone a remote repository like `clone`, asking `callbacks` for the
+    /// credentials the remote requires
+    pub fn clone_with_callbacks(url: &str, local_path: &str,
+                                callbacks: &mut remote::RemoteCallbacks)
+        -> Result<Repository, (~str, GitError)>
+    {
+        global_init();
+        let mut callbacks = callbacks;
+        let mut checkout_opts = checkout::CheckoutOptions::new();
+        checkout_opts.strategy = ~[checkout::GIT_CHECKOUT_SAFE];
+        let fetch_opts = remote::FetchOptions::new();
+        checkout::with_c_checkout_opts(&checkout_opts, |c_checkout_opts| {
+            unsafe {
+                let c_opts = ext::git_clone_options {
+                    version: ext::GIT_CLONE_OPTIONS_VERSION,
+                    checkout_opts: *c_checkout_opts,
+                    fetch_opts: remote::to_c_fetch_options(&fetch_opts, &mut callbacks),
+                    bare: 0,
+                    local: ext::GIT_CLONE_LOCAL_AUTO,
+                    checkout_branch: ptr::null(),
+                    repository_cb: ptr::null(),
+                    repository_cb_payload: ptr::null(),
+                    remote_cb: ptr::null(),
+                    remote_cb_payload: ptr::null(),
+                };
+                let mut ptr_to_repo: *ext::git_repository = ptr::null();
+                url.with_c_str(|c_url| {
+                    local_path.with_c_str(|c_path| {
+                        if ext::git_clone(&mut ptr_to_repo, c_url, c_path, &c_opts) == 0 {
+                            Ok( Repository::new(ptr_to_repo) )
+                        } else {
+                            Err( last_error() )
+                        }
+                    })
+                })
+            }
+        })
+    }
+
     fn new(repo: *ext::git_repository) -> Repository {
-        Repository { repo: repo }
+        Repository { repo: repo, owned: true }
+    }
+
+    // a handle on a repository that is not freed with it
+    fn borrowed(repo: *ext::git_repository) -> Repository {
+        Repository { repo: repo, owned: false }
     }
 
     /// Get the path of this repository
@@ -327,7 +477,7 @@ impl Repository {
 
             match ext::git_repository_head(&mut ptr_to_ref, self.repo) {
                 0 => Some( ~Reference::new(ptr_to_ref, self) ),
-                ext::GIT_EORPHANEDHEAD => None,
+                ext::GIT_EUNBORNBRANCH => None,
                 ext::GIT_ENOTFOUND => None,
                 _ => {
                     git_error::cond.raise(last_error());
@@ -418,7 +568,7 @@ impl Repository {
         unsafe {
             match ext::git_checkout_head(self.repo, ptr::null()) {
                 0 => true,
-                ext::GIT_EORPHANEDHEAD => false,
+                ext::GIT_EUNBORNBRANCH => false,
                 _ => {
                     git_error::cond.raise(last_error());
                     false
@@ -464,6 +614,37 @@ impl Repository {
         }
     }
 
+    /// Determines the status of a git repository - ie, whether an operation
+    /// (merge, cherry-pick, etc) is in progress.
+    pub fn state(&self) -> RepositoryState {
+        unsafe {
+            match ext::git_repository_state(self.repo) {
+                1 => GIT_REPOSITORY_STATE_MERGE,
+                2 => GIT_REPOSITORY_STATE_REVERT,
+                3 => GIT_REPOSITORY_STATE_CHERRY_PICK,
+                4 => GIT_REPOSITORY_STATE_BISECT,
+                5 => GIT_REPOSITORY_STATE_REBASE,
+                6 => GIT_REPOSITORY_STATE_REBASE_INTERACTIVE,
+                7 => GIT_REPOSITORY_STATE_REBASE_MERGE,
+                8 => GIT_REPOSITORY_STATE_APPLY_MAILBOX,
+                9 => GIT_REPOSITORY_STATE_APPLY_MAILBOX_OR_REBASE,
+                _ => GIT_REPOSITORY_STATE_NONE,
+            }
+        }
+    }
+
+    /// Remove all the metadata associated with an ongoing command like merge,
+    /// revert, cherry-pick, etc.  For example: MERGE_HEAD, MERGE_MSG, etc.
+    ///
+    /// raises git_error on error
+    pub fn state_cleanup(&self) {
+        unsafe {
+            if ext::git_repository_state_cleanup(self.repo) != 0 {
+                git_error::cond.raise(last_error());
+            }
+        }
+    }
+
     /// Create a new branch pointing at a target commit
     ///
     /// A new direct reference will be created pointing to
@@ -498,14 +679,12 @@ impl Repository {
     /// given the name of a local branch reference.
     pub fn upstream_name(&self, canonical_branch_name: &str) -> Option<~str>
     {
-        let mut buf: [c_char, ..1024] = [0, ..1024];
+        let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
         canonical_branch_name.with_c_str(|c_name| {
-            let v = buf.as_mut_ptr();
             unsafe {
-                let res = ext::git_branch_upstream_name(v, 1024, self.repo, c_name);
-                if res >= 0 {
-                    let ptr: *u8 = cast::transmute(v);
-                    Some( str::raw::from_buf_len(ptr, res as uint) )
+                let res = ext::git_branch_upstream_name(&mut buf, self.repo, c_name);
+                if res == 0 {
+                    Some( from_c_buf(&mut buf) )
                 } else if res == ext::GIT_ENOTFOUND {
                     None
                 } else {
@@ -522,14 +701,11 @@ impl Repository {
     pub fn git_branch_remote_name(&self, canonical_branch_name: &str)
         -> Result<~str, (~str, GitError)>
     {
-        let mut buf: [c_char, ..1024] = [0, ..1024];
+        let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
         canonical_branch_name.with_c_str(|c_name| {
-            let v = buf.as_mut_ptr();
             unsafe {
-                let res = ext::git_branch_remote_name(v, 1024, self.repo, c_name);
-                if res >= 0 {
-                    let ptr: *u8 = cast::transmute(v);
-                    Ok( str::raw::from_buf_len(ptr, res as uint) )
+                if ext::git_branch_remote_name(&mut buf, self.repo, c_name) == 0 {
+                    Ok( from_c_buf(&mut buf) )
                 } else {
                     Err( last_error() )
                 }
@@ -559,7 +735,7 @@ impl Repository {
         let mut ptr: *ext::git_blob = ptr::null();
         relative_path.with_c_str(|c_path| {
             unsafe {
-                if ext::git_blob_create_fromworkdir(&mut oid, self.repo, c_path) == 0 {
+                if ext::git_blob_create_from_workdir(&mut oid, self.repo, c_path) == 0 {
                     if ext::git_blob_lookup(&mut ptr, self.repo, &oid) != 0 {
                         fail!(~"blob lookup failure");
                     }
@@ -580,7 +756,7 @@ impl Repository {
         let mut ptr: *ext::git_blob = ptr::null();
         relative_path.with_c_str(|c_path| {
             unsafe {
-                if ext::git_blob_create_fromdisk(&mut oid, self.repo, c_path) == 0 {
+                if ext::git_blob_create_from_disk(&mut oid, self.repo, c_path) == 0 {
                     if ext::git_blob_lookup(&mut ptr, self.repo, &oid) != 0 {
                         fail!(~"blob lookup failure");
                     }
@@ -592,6 +768,89 @@ impl Repository {
         })
     }
 
+    /// Write a blob to the ODB from chunks of content,
+    /// without holding all of the content in memory
+    ///
+    /// `callback` fills the buffer it gets with the next chunk of content and
+    /// returns the number of bytes it wrote, or 0 once the content is complete.
+    /// If it returns an error, nothing is stored and the error is returned.
+    ///
+    /// hintpath: the path the content belongs to, relative to the working directory.
+    ///  If given, the filters configured for this path, like CRLF conversion,
+    ///  are applied to the content.
+    pub fn blob_create_fromchunks<'r>(&'r self, hintpath: Option<&str>,
+                                      callback: |&mut [u8]| -> Result<uint, (~str, GitError)>)
+        -> Result<~Blob<'r>, (~str, GitError)>
+    {
+        let mut writer = match self.blob_writer(hintpath) {
+            Ok(w) => w,
+            Err(e) => return Err(e),
+        };
+        let mut buf = vec::from_elem(BLOB_CHUNK_SZ, 0u8);
+        loop {
+            let len = match callback(buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                // dropping the writer discards what was written so far
+                Err(e) => return Err(e),
+            };
+            match writer.write_chunk(buf.slice_to(len)) {
+                Ok(()) => (),
+                Err(e) => return Err(e),
+            }
+        }
+        let oid = match writer.commit() {
+            Ok(oid) => oid,
+            Err(e) => return Err(e),
+        };
+        let mut ptr: *ext::git_blob = ptr::null();
+        unsafe {
+            if ext::git_blob_lookup(&mut ptr, self.repo, &oid) == 0 {
+                Ok( ~Blob::new(ptr, self) )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Write a blob to the ODB with the content read from `reader` until its end
+    ///
+    /// An I/O error raised by `reader` is returned as an error with the
+    /// GITERR_OS class, and nothing is stored.
+    ///
+    /// hintpath: see `blob_create_fromchunks`
+    pub fn blob_create_fromreader<'r>(&'r self, hintpath: Option<&str>, reader: &mut Reader)
+        -> Result<~Blob<'r>, (~str, GitError)>
+    {
+        self.blob_create_fromchunks(hintpath, |buf| {
+            let mut error = None;
+            let read = io::io_error::cond.trap(|e| error = Some(e)).inside(|| reader.read(buf));
+            match (error, read) {
+                (Some(e), _) => Err( (e.to_str(), GITERR_OS) ),
+                (None, Some(n)) => Ok(n),
+                (None, None) => Ok(0),
+            }
+        })
+    }
+
+    /// Create a stream to write a blob to the ODB through the `Writer` interface
+    ///
+    /// hintpath: see `blob_create_fromchunks`
+    pub fn blob_writer<'r>(&'r self, hintpath: Option<&str>)
+        -> Result<blob::BlobWriter<'r>, (~str, GitError)>
+    {
+        let mut stream: *ext::git_writestream = ptr::null();
+        with_opt_c_str(hintpath, |c_hintpath| {
+            unsafe {
+                if ext::git_blob_create_from_stream(&mut stream, self.repo, c_hintpath) == 0 {
+                    Ok( blob::BlobWriter::new(stream, self) )
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
     /// Write an in-memory buffer to the ODB as a blob
     pub fn blob_create_frombuffer<'r>(&'r self, buffer: &[u8])
         -> Result<~Blob<'r>, (~str, GitError)>
@@ -601,7 +860,7 @@ impl Repository {
         let len = buffer.len() as u64;
         unsafe {
             let buf:*c_void = cast::transmute(v);
-            if ext::git_blob_create_frombuffer(&mut oid, self.repo, buf, len) == 0 {
+            if ext::git_blob_create_from_buffer(&mut oid, self.repo, buf, len) == 0 {
                 let mut ptr: *ext::git_blob = ptr::null();
                 if ext::git_blob_lookup(&mut ptr, self.repo, &oid) != 0 {
                     fail!(~"blob lookup failure");
@@ -649,18 +908,21 @@ impl Repository {
             parents: &[~Commit<'r>]) -> OID
     {
         unsafe {
-            let c_author = signature::to_c_sig(author);
-            let c_committer = signature::to_c_sig(committer);
             let mut oid = OID { id: [0, .. 20] };
             let c_parents = parents.map(|p| { p.commit });
             let parent_ptr = c_parents.as_ptr();
-            let len = c_parents.len() as c_int;
+            let len = c_parents.len() as size_t;
             let res =
-            with_opt_c_str(update_ref, |ref_ptr| {
-                with_opt_c_str(message_encoding, |enc_ptr| {
-                    message.with_c_str(|msg_ptr| {
-                        ext::git_commit_create(&mut oid, self.repo, ref_ptr,
-                            &c_author, &c_committer, enc_ptr, msg_ptr, tree.tree, len, parent_ptr)
+            signature::with_c_sig(author, |c_author| {
+                signature::with_c_sig(committer, |c_committer| {
+                    with_opt_c_str(update_ref, |ref_ptr| {
+                        with_opt_c_str(message_encoding, |enc_ptr| {
+                            message.with_c_str(|msg_ptr| {
+                                ext::git_commit_create(&mut oid, self.repo, ref_ptr,
+                                    c_author, c_committer, enc_ptr, msg_ptr, tree.tree,
+                                    len, parent_ptr)
+                            })
+                        })
                     })
                 })
             });
@@ -671,12 +933,113 @@ impl Repository {
             oid
         }
     }
+
+    /// Find a merge base between two commits
+    ///
+    /// Returns None if the commits have no common ancestor
+    pub fn merge_base(&self, one: &OID, two: &OID) -> Option<OID>
+    {
+        let mut oid = OID { id: [0, ..20] };
+        unsafe {
+            match ext::git_merge_base(&mut oid, self.repo, one, two) {
+                0 => Some(oid),
+                ext::GIT_ENOTFOUND => None,
+                _ => {
+                    git_error::cond.raise(last_error());
+                    None
+                },
+            }
+        }
+    }
+
+    /// Find all merge bases given a list of commits
+    ///
+    /// Returns an empty vector if the commits have no common ancestor
+    pub fn merge_bases_many(&self, input: &[OID]) -> ~[OID]
+    {
+        let mut arr = ext::git_oidarray { ids: ptr::null(), count: 0 };
+        unsafe {
+            match ext::git_merge_bases_many(&mut arr, self.repo, input.len() as size_t,
+                                            input.as_ptr()) {
+                0 => {
+                    let bases = vec::raw::from_buf_raw(arr.ids, arr.count as uint);
+                    ext::git_oidarray_dispose(&mut arr);
+                    bases
+                },
+                ext::GIT_ENOTFOUND => ~[],
+                _ => {
+                    git_error::cond.raise(last_error());
+                    ~[]
+                },
+            }
+        }
+    }
+
+    /// Find a merge base in preparation for an octopus merge
+    ///
+    /// Returns None if the commits have no common ancestor
+    pub fn merge_base_octopus(&self, input: &[OID]) -> Option<OID>
+    {
+        let mut oid = OID { id: [0, ..20] };
+        unsafe {
+            match ext::git_merge_base_octopus(&mut oid, self.repo, input.len() as size_t,
+                                              input.as_ptr()) {
+                0 => Some(oid),
+                ext::GIT_ENOTFOUND => None,
+                _ => {
+                    git_error::cond.raise(last_error());
+                    None
+                },
+            }
+        }
+    }
+
+    /// Count the number of unique commits between two commit objects
+    ///
+    /// There is no need for branches containing the commits to have any
+    /// upstream relationship, but it helps to think of one as a branch and
+    /// the other as its upstream.
+    ///
+    /// Returns (ahead, behind), where `ahead` is the number of commits reachable from
+    /// `local` but not from `upstream`, and `behind` the other way around.
+    pub fn graph_ahead_behind(&self, local: &OID, upstream: &OID)
+        -> Result<(uint, uint), (~str, GitError)>
+    {
+        let mut ahead: size_t = 0;
+        let mut behind: size_t = 0;
+        unsafe {
+            if ext::git_graph_ahead_behind(&mut ahead, &mut behind, self.repo, local,
+                                           upstream) == 0 {
+                Ok( (ahead as uint, behind as uint) )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Determine if a commit is the descendant of another commit.
+    ///
+    /// A commit is not considered a descendant of itself.
+    pub fn graph_descendant_of(&self, commit: &OID, ancestor: &OID) -> bool
+    {
+        unsafe {
+            let res = ext::git_graph_descendant_of(self.repo, commit, ancestor);
+            if res < 0 {
+                git_error::cond.raise(last_error());
+                false
+            } else {
+                res != 0
+            }
+        }
+    }
 }
 
 impl Drop for Repository {
     fn drop(&mut self) {
         unsafe {
-            ext::git_repository_free(self.repo);
+            if self.owned {
+                ext::git_repository_free(self.repo);
+            }
         }
     }
 }
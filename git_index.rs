@@ -1,10 +1,259 @@
 use ext;
-use std::ptr;
-use super::{GitError, OID};
-use super::{git_error, last_error};
+use std::{ptr, str, cast};
+use std::libc::{c_char, c_int, c_uint, c_void, size_t};
+use super::{GitError, OID, FileMode, WalkMode, WalkStop};
+use super::{git_error, last_error, with_c_strarray, DiffList};
 use super::Repository;
 use super::tree::Tree;
 
+/// Flags for `GitIndex::add_all`
+pub enum IndexAddOption {
+    GIT_INDEX_ADD_DEFAULT = 0,
+    /// Add files that are ignored by .gitignore rules
+    GIT_INDEX_ADD_FORCE = (1 << 0),
+    /// Match the pathspecs exactly instead of as fnmatch patterns
+    GIT_INDEX_ADD_DISABLE_PATHSPEC_MATCH = (1 << 1),
+    /// Fail if a pathspec names an ignored file, like `git add` without `-f`
+    GIT_INDEX_ADD_CHECK_PATHSPEC = (1 << 2),
+}
+
+/// Time of an index entry, with nanosecond precision
+pub struct IndexTime {
+    seconds: i64,
+    nanoseconds: u32,
+}
+
+/// An entry in the index, with the stat data of the file it was read from.
+///
+/// The conflict stage of the entry is stored in bits 12-13 of `flags`,
+/// see `stage()`.
+pub struct IndexEntry {
+    ctime: IndexTime,
+    mtime: IndexTime,
+
+    dev: u32,
+    ino: u32,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    file_size: i64,
+
+    oid: OID,
+
+    flags: u16,
+    flags_extended: u16,
+
+    path: ~str,
+}
+
+static GIT_IDXENTRY_STAGEMASK: u16 = 0x3000;
+static GIT_IDXENTRY_STAGESHIFT: u16 = 12;
+
+impl IndexEntry {
+    /// Create an entry for `path` pointing at `oid`, with zeroed stat data
+    pub fn new(path: &str, oid: &OID, mode: FileMode) -> IndexEntry
+    {
+        IndexEntry {
+            ctime: IndexTime { seconds: 0, nanoseconds: 0 },
+            mtime: IndexTime { seconds: 0, nanoseconds: 0 },
+            dev: 0,
+            ino: 0,
+            mode: mode as u32,
+            uid: 0,
+            gid: 0,
+            file_size: 0,
+            oid: *oid,
+            flags: 0,
+            flags_extended: 0,
+            path: path.to_owned(),
+        }
+    }
+
+    /// Conflict stage of the entry.
+    /// 0 for a normal entry, 1 for the ancestor, 2 for ours and 3 for theirs
+    pub fn stage(&self) -> uint
+    {
+        ((self.flags & GIT_IDXENTRY_STAGEMASK) >> GIT_IDXENTRY_STAGESHIFT) as uint
+    }
+}
+
+unsafe fn from_c_entry(c_entry: *ext::git_index_entry) -> Option<IndexEntry>
+{
+    if c_entry.is_null() {
+        return None;
+    }
+    Some( IndexEntry {
+        ctime: IndexTime {
+            seconds: (*c_entry).ctime.seconds as i64,
+            nanoseconds: (*c_entry).ctime.nanoseconds,
+        },
+        mtime: IndexTime {
+            seconds: (*c_entry).mtime.seconds as i64,
+            nanoseconds: (*c_entry).mtime.nanoseconds,
+        },
+        dev: (*c_entry).dev,
+        ino: (*c_entry).ino,
+        mode: (*c_entry).mode,
+        uid: (*c_entry).uid,
+        gid: (*c_entry).gid,
+        file_size: (*c_entry).file_size as i64,
+        oid: (*c_entry).id,
+        flags: (*c_entry).flags,
+        flags_extended: (*c_entry).flags_extended,
+        path: str::raw::from_c_str((*c_entry).path),
+    })
+}
+
+fn with_c_entry<T>(entry: Option<&IndexEntry>, f: |*ext::git_index_entry| -> T) -> T
+{
+    match entry {
+        None => f(ptr::null()),
+        Some(e) => e.path.with_c_str(|c_path| {
+            let c_entry = ext::git_index_entry {
+                ctime: ext::git_index_time {
+                    seconds: e.ctime.seconds as i32,
+                    nanoseconds: e.ctime.nanoseconds,
+                },
+                mtime: ext::git_index_time {
+                    seconds: e.mtime.seconds as i32,
+                    nanoseconds: e.mtime.nanoseconds,
+                },
+                dev: e.dev,
+                ino: e.ino,
+                mode: e.mode,
+                uid: e.uid,
+                gid: e.gid,
+                file_size: e.file_size as u32,
+                id: e.oid,
+                flags: e.flags,
+                flags_extended: e.flags_extended,
+                path: c_path,
+            };
+            f(&c_entry)
+        }),
+    }
+}
+
+/// The index entries of a conflicted file.
+/// A side is None if the file does not exist on that side.
+pub struct IndexConflict {
+    ancestor: Option<IndexEntry>,
+    our: Option<IndexEntry>,
+    their: Option<IndexEntry>,
+}
+
+/// Error returned by `GitIndex::write_tree`
+#[deriving(Eq)]
+pub enum WriteTreeError {
+    /// The index holds files in conflict, which a tree cannot represent
+    Unmerged(~str),
+    /// Any other failure, as reported by libgit2
+    Other(~str, GitError),
+}
+
+/// Iterator over the conflicts in an index
+pub struct IndexConflicts<'r> {
+    priv iter: *ext::git_index_conflict_iterator,
+    priv owner: &'r GitIndex<'r>,
+}
+
+impl<'r> Iterator<IndexConflict> for IndexConflicts<'r> {
+    fn next(&mut self) -> Option<IndexConflict> {
+        unsafe {
+            let mut ancestor: *ext::git_index_entry = ptr::null();
+            let mut our: *ext::git_index_entry = ptr::null();
+            let mut their: *ext::git_index_entry = ptr::null();
+            match ext::git_index_conflict_next(&mut ancestor, &mut our, &mut their, self.iter) {
+                0 => Some( IndexConflict {
+                    ancestor: from_c_entry(ancestor),
+                    our: from_c_entry(our),
+                    their: from_c_entry(their),
+                }),
+                ext::GIT_ITEROVER => None,
+                _ => {
+                    git_error::cond.raise(last_error());
+                    None
+                },
+            }
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<'r> Drop for IndexConflicts<'r> {
+    fn drop(&mut self) {
+        unsafe {
+            ext::git_index_conflict_iterator_free(self.iter);
+        }
+    }
+}
+
+/// A resolve undo entry, recording the state of a conflicted file before it was resolved.
+/// A mode of 0 means the file did not exist on that side.
+pub struct IndexReucEntry {
+    mode: [u32, ..3],
+    oid: [OID, ..3],
+    path: ~str,
+}
+
+unsafe fn from_c_reuc(c_reuc: *ext::git_index_reuc_entry) -> Option<IndexReucEntry>
+{
+    if c_reuc.is_null() {
+        None
+    } else {
+        Some( IndexReucEntry {
+            mode: [(*c_reuc).mode[0] as u32, (*c_reuc).mode[1] as u32,
+                   (*c_reuc).mode[2] as u32],
+            oid: (*c_reuc).oid,
+            path: str::raw::from_c_str((*c_reuc).path),
+        })
+    }
+}
+
+extern "C" fn index_matched_path_cb(path: *c_char, matched_pathspec: *c_char,
+                                    payload: *c_void) -> c_int
+{
+    unsafe {
+        let callback: &mut |&str, &str| -> WalkMode = cast::transmute(payload);
+        let path = str::raw::from_c_str(path);
+        let matched = if matched_pathspec.is_null() {
+            ~""
+        } else {
+            str::raw::from_c_str(matched_pathspec)
+        };
+        (*callback)(path, matched) as c_int
+    }
+}
+
+// run a bulk operation with `callback` as its payload. libgit2 reports a
+// callback that stopped the operation like an error, but WalkStop is not one.
+fn bulk_apply(callback: |&str, &str| -> WalkMode, apply: |*c_void| -> c_int)
+    -> Result<(), (~str, GitError)>
+{
+    let mut callback = callback;
+    let mut stopped = false;
+    let res = {
+        let mut wrapper: |&str, &str| -> WalkMode = |path, matched| {
+            let walk = callback(path, matched);
+            match walk {
+                WalkStop => stopped = true,
+                _ => (),
+            }
+            walk
+        };
+        unsafe {
+            apply(cast::transmute(&mut wrapper))
+        }
+    };
+    if res == 0 || stopped {
+        Ok(())
+    } else {
+        unsafe {
+            Err( last_error() )
+        }
+    }
+}
+
 pub struct GitIndex<'r> {
     priv index: *ext::git_index,
     priv owner: &'r Repository,
@@ -64,6 +313,79 @@ impl<'r> GitIndex<'r> {
         })
     }
 
+    /// Add or update index entries matching files in the working directory.
+    ///
+    /// The `pathspecs` are a list of file names or shell glob patterns that will
+    /// be matched against files in the repository's working directory. Each file
+    /// that matches will be added to the index (either updating an existing entry
+    /// or adding a new entry). An empty list adds every file.
+    ///
+    /// Files that are ignored will be skipped (unlike `add_bypath`). If a file is
+    /// already tracked in the index, then it *will* be updated even if it is ignored.
+    /// Pass GIT_INDEX_ADD_FORCE to skip the checking of ignore rules.
+    ///
+    /// With GIT_INDEX_ADD_CHECK_PATHSPEC, an exact pathspec that names an ignored
+    /// file makes the call fail, emulating `git add` without `-f`.
+    ///
+    /// `callback` is invoked with the path and the pathspec that matched it,
+    /// before the path is added. Return WalkPass to add the file, WalkSkip to
+    /// skip it, or WalkStop to stop processing the remaining files, which is
+    /// not an error: the files processed so far stay in the index.
+    ///
+    /// This method will fail in bare index instances.
+    pub fn add_all(&self, pathspecs: &[&str], flags: &[IndexAddOption],
+                   callback: |&str, &str| -> WalkMode) -> Result<(), (~str, GitError)>
+    {
+        let c_flags = flags.iter().fold(0, |acc, &f| acc | (f as c_uint));
+        bulk_apply(callback, |payload| {
+            with_c_strarray(pathspecs, |c_pathspecs| {
+                unsafe {
+                    ext::git_index_add_all(self.index, c_pathspecs, c_flags,
+                                           cast::transmute(index_matched_path_cb), payload)
+                }
+            })
+        })
+    }
+
+    /// Update all index entries to match the working directory
+    ///
+    /// Every tracked file matching `pathspecs` is re-read from the working directory,
+    /// and entries whose file has been deleted are removed from the index.
+    /// This is like `git add -u`.
+    ///
+    /// `callback` is invoked for each matching entry, as in `add_all`.
+    ///
+    /// This method will fail in bare index instances.
+    pub fn update_all(&self, pathspecs: &[&str], callback: |&str, &str| -> WalkMode)
+        -> Result<(), (~str, GitError)>
+    {
+        bulk_apply(callback, |payload| {
+            with_c_strarray(pathspecs, |c_pathspecs| {
+                unsafe {
+                    ext::git_index_update_all(self.index, c_pathspecs,
+                                              cast::transmute(index_matched_path_cb), payload)
+                }
+            })
+        })
+    }
+
+    /// Remove all index entries matching `pathspecs`
+    ///
+    /// `callback` is invoked for each matching entry before it is removed,
+    /// as in `add_all`.
+    pub fn remove_all(&self, pathspecs: &[&str], callback: |&str, &str| -> WalkMode)
+        -> Result<(), (~str, GitError)>
+    {
+        bulk_apply(callback, |payload| {
+            with_c_strarray(pathspecs, |c_pathspecs| {
+                unsafe {
+                    ext::git_index_remove_all(self.index, c_pathspecs,
+                                              cast::transmute(index_matched_path_cb), payload)
+                }
+            })
+        })
+    }
+
     /// Read a tree into the index file with stats
     ///
     /// The current index contents will be replaced by the specified tree.
@@ -95,32 +417,171 @@ impl<'r> GitIndex<'r> {
     /// tree objects for each of the subtrees stored in the index,
     /// and returns the root tree. This is the Tree that can be used e.g. to create a commit.
     ///
-    /// The index instance cannot be bare, and needs to be associated
-    /// to an existing repository.
+    /// The trees are written to the repository the index was obtained from.
+    /// This also works for in-memory indexes, such as the result of `merge_trees`.
     ///
     /// The index must not contain any file in conflict.
-    pub fn write_tree<'r>(&'r self) -> Result<~Tree<'r>, (~str, GitError)> {
+    /// If it does, Err(Unmerged) is returned.
+    pub fn write_tree<'r>(&'r self) -> Result<~Tree<'r>, WriteTreeError> {
         unsafe {
             let mut oid = OID { id: [0, .. 20] };
-            if ext::git_index_write_tree(&mut oid, self.index) == 0 {
-                let mut ptr_to_tree: *ext::git_tree = ptr::null();
-                if ext::git_tree_lookup(&mut ptr_to_tree, self.owner.repo, &oid) == 0 {
-                    Ok( ~Tree::new(ptr_to_tree, self.owner) )
-                } else {
-                    Err( last_error() )
-                }
+            match ext::git_index_write_tree_to(&mut oid, self.index, self.owner.repo) {
+                0 => {
+                    let mut ptr_to_tree: *ext::git_tree = ptr::null();
+                    if ext::git_tree_lookup(&mut ptr_to_tree, self.owner.repo, &oid) == 0 {
+                        Ok( ~Tree::new(ptr_to_tree, self.owner) )
+                    } else {
+                        let (message, klass) = last_error();
+                        Err( Other(message, klass) )
+                    }
+                },
+                ext::GIT_EUNMERGED => {
+                    let (message, _) = last_error();
+                    Err( Unmerged(message) )
+                },
+                _ => {
+                    let (message, klass) = last_error();
+                    Err( Other(message, klass) )
+                },
+            }
+        }
+    }
+
+    /// Create a diff list between the index and the working directory
+    ///
+    /// The working directory files are compared to the index entries after
+    /// being run through the filters, the same as when they are added.
+    /// Untracked and ignored files are not included.
+    pub fn diff_to_workdir(&self) -> Result<~DiffList, (~str, GitError)> {
+        unsafe {
+            let mut diff: *ext::git_diff = ptr::null();
+            if ext::git_diff_index_to_workdir(&mut diff, self.owner.repo, self.index,
+                                              ptr::null()) == 0 {
+                Ok( ~DiffList { difflist: diff } )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Determine if the index contains entries representing file conflicts.
+    pub fn has_conflicts(&self) -> bool {
+        unsafe {
+            ext::git_index_has_conflicts(self.index) != 0
+        }
+    }
+
+    /// Iterate over the conflicts in the index
+    pub fn conflicts<'a>(&'a self) -> Result<IndexConflicts<'a>, (~str, GitError)> {
+        unsafe {
+            let mut iter: *ext::git_index_conflict_iterator = ptr::null();
+            if ext::git_index_conflict_iterator_new(&mut iter, self.index) == 0 {
+                Ok( IndexConflicts { iter: iter, owner: self } )
             } else {
                 Err( last_error() )
             }
         }
     }
 
+    /// Get the index entries that represent a conflict of a single file.
+    ///
+    /// Returns None if `path` is not in conflict.
+    pub fn conflict_get(&self, path: &str) -> Option<IndexConflict> {
+        path.with_c_str(|c_path| {
+            unsafe {
+                let mut ancestor: *ext::git_index_entry = ptr::null();
+                let mut our: *ext::git_index_entry = ptr::null();
+                let mut their: *ext::git_index_entry = ptr::null();
+                match ext::git_index_conflict_get(&mut ancestor, &mut our, &mut their,
+                                                  self.index, c_path) {
+                    0 => Some( IndexConflict {
+                        ancestor: from_c_entry(ancestor),
+                        our: from_c_entry(our),
+                        their: from_c_entry(their),
+                    }),
+                    ext::GIT_ENOTFOUND => None,
+                    _ => {
+                        git_error::cond.raise(last_error());
+                        None
+                    },
+                }
+            }
+        })
+    }
+
+    /// Add or update index entries to represent a conflict
+    ///
+    /// The entries are placed at stages 1, 2 and 3, replacing any
+    /// existing entry for the same path. Any side may be None, but not all of them.
+    ///
+    /// raises git_error on error
+    pub fn conflict_add(&self, ancestor: Option<&IndexEntry>, our: Option<&IndexEntry>,
+                        their: Option<&IndexEntry>) {
+        with_c_entry(ancestor, |c_ancestor| {
+            with_c_entry(our, |c_our| {
+                with_c_entry(their, |c_their| {
+                    unsafe {
+                        if ext::git_index_conflict_add(self.index, c_ancestor, c_our,
+                                                       c_their) != 0 {
+                            git_error::cond.raise(last_error())
+                        }
+                    }
+                })
+            })
+        })
+    }
+
+    /// Remove all conflict entries of `path`
+    ///
+    /// return true if successful, false if the path is not in conflict
+    pub fn conflict_remove(&self, path: &str) -> bool {
+        path.with_c_str(|c_path| {
+            unsafe {
+                ext::git_index_conflict_remove(self.index, c_path) == 0
+            }
+        })
+    }
+
+    /// Remove all conflicts in the index (entries with a stage greater than 0).
+    pub fn conflict_cleanup(&self) {
+        unsafe {
+            if ext::git_index_conflict_cleanup(self.index) != 0 {
+                git_error::cond.raise(last_error());
+            }
+        }
+    }
+
+    /// Get the count of resolve undo entries currently in the index.
+    pub fn reuc_entrycount(&self) -> uint {
+        unsafe {
+            ext::git_index_reuc_entrycount(self.index) as uint
+        }
+    }
+
+    /// Get a resolve undo entry from the index by its position
+    pub fn reuc_get_byindex(&self, n: uint) -> Option<IndexReucEntry> {
+        unsafe {
+            from_c_reuc(ext::git_index_reuc_get_byindex(self.index, n as size_t))
+        }
+    }
+
+    /// Get the resolve undo entry of `path`
+    pub fn reuc_get_bypath(&self, path: &str) -> Option<IndexReucEntry> {
+        path.with_c_str(|c_path| {
+            unsafe {
+                from_c_reuc(ext::git_index_reuc_get_bypath(self.index, c_path))
+            }
+        })
+    }
+
     /// Clear the contents (all the entries) of an index object.
     /// This clears the index object in memory; changes must be manually
     /// written to disk for them to take effect.
     pub fn clear(&self) {
         unsafe {
-            ext::git_index_clear(self.index);
+            if ext::git_index_clear(self.index) != 0 {
+                git_error::cond.raise(last_error());
+            }
         }
     }
 }
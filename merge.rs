@@ -0,0 +1,296 @@
+use std::{ptr, cast};
+use std::libc::{c_uint, c_void, size_t};
+use ext;
+use super::{OID, GitError, last_error};
+use super::Repository;
+use super::git_index::GitIndex;
+use super::tree::Tree;
+use super::commit::Commit;
+use super::reference;
+use super::reference::Reference;
+use super::checkout::{CheckoutOptions, with_c_checkout_opts};
+
+pub enum MergeFlag {
+    /** Detect renames between the ancestor and each side of the merge */
+    GIT_MERGE_FIND_RENAMES = (1 << 0),
+    /** Stop at the first conflict instead of recording it in the index */
+    GIT_MERGE_FAIL_ON_CONFLICT = (1 << 1),
+    /** Don't write the resolved conflicts to the REUC extension of the index */
+    GIT_MERGE_SKIP_REUC = (1 << 2),
+    /** Use the first merge base instead of merging several bases recursively */
+    GIT_MERGE_NO_RECURSIVE = (1 << 3),
+}
+
+/// How to resolve conflicting hunks within a file.
+/// Files that cannot be merged by hunks (e.g. binary files) still conflict.
+pub enum MergeFileFavor {
+    /** Record a conflict in the index */
+    GIT_MERGE_FILE_FAVOR_NORMAL = 0,
+    /** Take our side of conflicting hunks */
+    GIT_MERGE_FILE_FAVOR_OURS = 1,
+    /** Take their side of conflicting hunks */
+    GIT_MERGE_FILE_FAVOR_THEIRS = 2,
+    /** Take both sides of conflicting hunks, one after the other */
+    GIT_MERGE_FILE_FAVOR_UNION = 3,
+}
+
+pub enum MergeFileFlag {
+    /** Create standard conflicted merge files */
+    GIT_MERGE_FILE_STYLE_MERGE = (1 << 0),
+    /** Create diff3-style conflicted merge files */
+    GIT_MERGE_FILE_STYLE_DIFF3 = (1 << 1),
+    /** Condense non-alphanumeric regions for simplified diff file */
+    GIT_MERGE_FILE_SIMPLIFY_ALNUM = (1 << 2),
+    /** Ignore all whitespace */
+    GIT_MERGE_FILE_IGNORE_WHITESPACE = (1 << 3),
+    /** Ignore changes in amount of whitespace */
+    GIT_MERGE_FILE_IGNORE_WHITESPACE_CHANGE = (1 << 4),
+    /** Ignore whitespace at end of line */
+    GIT_MERGE_FILE_IGNORE_WHITESPACE_EOL = (1 << 5),
+    /** Use the "patience diff" algorithm */
+    GIT_MERGE_FILE_DIFF_PATIENCE = (1 << 6),
+    /** Take extra time to find minimal diff */
+    GIT_MERGE_FILE_DIFF_MINIMAL = (1 << 7),
+}
+
+pub struct MergeOptions {
+    flags: ~[MergeFlag],
+    /// Similarity to consider a file renamed, in percent
+    rename_threshold: uint,
+    /// Maximum similarity sources to examine for renames
+    target_limit: uint,
+    file_favor: MergeFileFavor,
+    file_flags: ~[MergeFileFlag],
+}
+
+impl MergeOptions {
+    pub fn new() -> MergeOptions {
+        MergeOptions {
+            flags: ~[GIT_MERGE_FIND_RENAMES],
+            rename_threshold: 50,
+            target_limit: 200,
+            file_favor: GIT_MERGE_FILE_FAVOR_NORMAL,
+            file_flags: ~[],
+        }
+    }
+}
+
+pub fn to_c_merge_opts(opts: &MergeOptions) -> ext::git_merge_options {
+    ext::git_merge_options {
+        version: ext::GIT_MERGE_OPTIONS_VERSION,
+        flags: opts.flags.iter().fold(0, |acc, &f| acc | (f as u32)),
+        rename_threshold: opts.rename_threshold as c_uint,
+        target_limit: opts.target_limit as c_uint,
+        metric: ptr::null::<c_void>(),
+        recursion_limit: 0,
+        default_driver: ptr::null(),
+        file_favor: opts.file_favor as c_uint,
+        file_flags: opts.file_flags.iter().fold(0, |acc, &f| acc | (f as u32)),
+    }
+}
+
+/// The result of `merge_analysis`.
+///
+/// Several flags may be set at once, e.g. a fast-forward is always
+/// a possible normal merge as well.
+pub struct MergeAnalysis {
+    /// A "normal" merge; both HEAD and the given merge input have diverged
+    /// from their common ancestor. The divergent commits must be merged.
+    normal: bool,
+    /// All given merge inputs are reachable from HEAD, meaning the
+    /// repository is up-to-date and no merge needs to be performed.
+    up_to_date: bool,
+    /// The given merge input is a fast-forward from HEAD and no merge
+    /// needs to be performed. Instead, the client can check out the given merge input.
+    fastforward: bool,
+    /// The HEAD of the current repository is "unborn" and does not point to
+    /// a valid commit. No merge can be performed, but the caller may wish
+    /// to simply set HEAD to the target commit(s).
+    unborn: bool,
+}
+
+/// The user's stated preference for merges, from the `merge.ff` configuration
+pub enum MergePreference {
+    /** No configuration was found that suggests a preferred behavior */
+    GIT_MERGE_PREFERENCE_NONE = 0,
+    /** There is a `merge.ff=false` configuration setting, suggesting that
+     *  the user does not want to allow a fast-forward merge */
+    GIT_MERGE_PREFERENCE_NO_FASTFORWARD = (1 << 0),
+    /** There is a `merge.ff=only` configuration setting, suggesting that
+     *  the user only wants fast-forward merges */
+    GIT_MERGE_PREFERENCE_FASTFORWARD_ONLY = (1 << 1),
+}
+
+/// A commit to merge, annotated with how it was looked up.
+/// The annotation is used to write a meaningful MERGE_MSG.
+pub struct AnnotatedCommit<'r> {
+    priv commit: *ext::git_annotated_commit,
+    priv owner: &'r Repository,
+}
+
+impl<'r> AnnotatedCommit<'r> {
+    /// Get the id of the commit
+    pub fn id<'r>(&self) -> &'r OID
+    {
+        unsafe {
+            cast::transmute(ext::git_annotated_commit_id(self.commit))
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<'r> Drop for AnnotatedCommit<'r> {
+    fn drop(&mut self) {
+        unsafe {
+            ext::git_annotated_commit_free(self.commit);
+        }
+    }
+}
+
+fn with_c_heads<T>(heads: &[~AnnotatedCommit], f: |**ext::git_annotated_commit, size_t| -> T)
+    -> T
+{
+    let c_heads = heads.map(|h| { h.commit });
+    f(c_heads.as_ptr(), c_heads.len() as size_t)
+}
+
+impl Repository {
+    /// Look up a commit to merge by its id
+    pub fn annotated_commit_lookup<'r>(&'r self, id: &OID)
+        -> Result<~AnnotatedCommit<'r>, (~str, GitError)>
+    {
+        let mut commit: *ext::git_annotated_commit = ptr::null();
+        unsafe {
+            if ext::git_annotated_commit_lookup(&mut commit, self.repo, id) == 0 {
+                Ok( ~AnnotatedCommit { commit: commit, owner: self } )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Look up the commit a reference points to, for merging.
+    /// The merge message will name the branch, e.g. "Merge branch 'topic'".
+    pub fn annotated_commit_from_ref<'r>(&'r self, reference: &Reference)
+        -> Result<~AnnotatedCommit<'r>, (~str, GitError)>
+    {
+        let mut commit: *ext::git_annotated_commit = ptr::null();
+        unsafe {
+            let c_ref = reference::raw_reference(reference);
+            if ext::git_annotated_commit_from_ref(&mut commit, self.repo, c_ref) == 0 {
+                Ok( ~AnnotatedCommit { commit: commit, owner: self } )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Analyzes the given branch(es) and determines the opportunities for
+    /// merging them into the HEAD of the repository.
+    ///
+    /// Returns the analysis, and the user's preference read from the configuration.
+    pub fn merge_analysis(&self, heads: &[~AnnotatedCommit])
+        -> Result<(MergeAnalysis, MergePreference), (~str, GitError)>
+    {
+        let mut c_analysis: c_uint = 0;
+        let mut c_preference: c_uint = 0;
+        with_c_heads(heads, |c_heads, len| {
+            unsafe {
+                if ext::git_merge_analysis(&mut c_analysis, &mut c_preference, self.repo,
+                                           c_heads, len) == 0 {
+                    let analysis = MergeAnalysis {
+                        normal: c_analysis & ext::GIT_MERGE_ANALYSIS_NORMAL != 0,
+                        up_to_date: c_analysis & ext::GIT_MERGE_ANALYSIS_UP_TO_DATE != 0,
+                        fastforward: c_analysis & ext::GIT_MERGE_ANALYSIS_FASTFORWARD != 0,
+                        unborn: c_analysis & ext::GIT_MERGE_ANALYSIS_UNBORN != 0,
+                    };
+                    let preference = match c_preference {
+                        1 => GIT_MERGE_PREFERENCE_NO_FASTFORWARD,
+                        2 => GIT_MERGE_PREFERENCE_FASTFORWARD_ONLY,
+                        _ => GIT_MERGE_PREFERENCE_NONE,
+                    };
+                    Ok( (analysis, preference) )
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Merges the given commit(s) into HEAD, writing the results into the working directory.
+    ///
+    /// Any changes are staged for commit and any conflicts are written to the index,
+    /// with conflict markers written into the conflicting files in the working directory.
+    /// Callers should inspect the repository's index after this completes,
+    /// resolve any conflicts and prepare a commit.
+    ///
+    /// MERGE_HEAD and MERGE_MSG are written, and the repository state becomes
+    /// GIT_REPOSITORY_STATE_MERGE until `state_cleanup` is called.
+    ///
+    /// This always performs a merge, use `merge_analysis` first to detect fast-forwards.
+    pub fn merge(&self, heads: &[~AnnotatedCommit], merge_opts: &MergeOptions,
+                 checkout_opts: &CheckoutOptions) -> Result<(), (~str, GitError)>
+    {
+        let c_merge_opts = to_c_merge_opts(merge_opts);
+        with_c_heads(heads, |c_heads, len| {
+            with_c_checkout_opts(checkout_opts, |c_checkout_opts| {
+                unsafe {
+                    if ext::git_merge(self.repo, c_heads, len, &c_merge_opts,
+                                      c_checkout_opts) == 0 {
+                        Ok(())
+                    } else {
+                        Err( last_error() )
+                    }
+                }
+            })
+        })
+    }
+
+    /// Merge two trees, producing an index that reflects the result of the merge.
+    ///
+    /// The index may be written as-is to the working directory or checked out.
+    /// If the index is to be converted to a tree, the caller should resolve any
+    /// conflicts that arose as part of the merge.
+    ///
+    /// The returned index is not backed by a file, and neither the repository
+    /// index nor the working directory is touched, so this works in bare repositories.
+    ///
+    /// ancestor: the common ancestor between the trees, or None if there is none
+    pub fn merge_trees<'r>(&'r self, ancestor: Option<&Tree>, ours: &Tree, theirs: &Tree,
+                           opts: &MergeOptions) -> Result<~GitIndex<'r>, (~str, GitError)>
+    {
+        let c_opts = to_c_merge_opts(opts);
+        let ancestor_ptr = match ancestor {
+            None => ptr::null(),
+            Some(t) => t.tree,
+        };
+        let mut index: *ext::git_index = ptr::null();
+        unsafe {
+            if ext::git_merge_trees(&mut index, self.repo, ancestor_ptr, ours.tree, theirs.tree,
+                                    &c_opts) == 0 {
+                Ok( ~GitIndex::new(index, self) )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Merge two commits, producing an index that reflects the result of the merge.
+    ///
+    /// The merge base of the commits is used as the ancestor.
+    /// As with `merge_trees`, no working directory is touched.
+    pub fn merge_commits<'r>(&'r self, ours: &Commit, theirs: &Commit, opts: &MergeOptions)
+        -> Result<~GitIndex<'r>, (~str, GitError)>
+    {
+        let c_opts = to_c_merge_opts(opts);
+        let mut index: *ext::git_index = ptr::null();
+        unsafe {
+            if ext::git_merge_commits(&mut index, self.repo, ours.commit, theirs.commit,
+                                      &c_opts) == 0 {
+                Ok( ~GitIndex::new(index, self) )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+}
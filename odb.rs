@@ -0,0 +1,269 @@
+use std::{ptr, vec, cast, cmp};
+use std::libc::{c_char, c_int, c_void, size_t};
+use ext;
+use super::{OID, OType, GIT_OBJ_BAD, GitError, git_error, last_error};
+use super::Repository;
+
+/// The object database of a repository, where the objects are stored
+/// in their raw form
+///
+/// Gives access to objects of any type without parsing them.
+pub struct Odb {
+    priv db: *ext::git_odb,
+}
+
+extern "C" fn foreach_cb(id: *OID, payload: *c_void) -> c_int
+{
+    unsafe {
+        let callback: &mut |&OID| -> bool = cast::transmute(payload);
+        if (*callback)(&*id) { 0 } else { 1 }
+    }
+}
+
+impl Odb {
+    /// Whether the object `id` is in the database
+    pub fn exists(&self, id: &OID) -> bool
+    {
+        unsafe {
+            ext::git_odb_exists(self.db, id) != 0
+        }
+    }
+
+    /// Get the type and the size of the object `id`, without reading its content
+    /// when the backend can avoid it
+    pub fn read_header(&self, id: &OID) -> Result<(OType, uint), (~str, GitError)>
+    {
+        let mut len: size_t = 0;
+        let mut otype = GIT_OBJ_BAD;
+        unsafe {
+            if ext::git_odb_read_header(&mut len, &mut otype, self.db, id) == 0 {
+                Ok( (otype, len as uint) )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Read the type and the raw content of the object `id`
+    pub fn read(&self, id: &OID) -> Result<(OType, ~[u8]), (~str, GitError)>
+    {
+        let mut obj: *ext::git_odb_object = ptr::null();
+        unsafe {
+            if ext::git_odb_read(&mut obj, self.db, id) == 0 {
+                let otype = ext::git_odb_object_type(obj);
+                let data = vec::raw::from_buf_raw(ext::git_odb_object_data(obj) as *u8,
+                                                  ext::git_odb_object_size(obj) as uint);
+                ext::git_odb_object_free(obj);
+                Ok( (otype, data) )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Store `data` as an object of type `otype`, and return its id.
+    ///
+    /// The content is stored as is: it is not checked to be a valid object of that type.
+    pub fn write(&self, otype: OType, data: &[u8]) -> Result<OID, (~str, GitError)>
+    {
+        let mut oid = OID { id: [0, ..20] };
+        unsafe {
+            if ext::git_odb_write(&mut oid, self.db, data.as_ptr() as *c_void,
+                                  data.len() as size_t, otype) == 0 {
+                Ok(oid)
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Call `callback` with the id of every object in the database, loose or packed,
+    /// until it returns false
+    pub fn foreach(&self, callback: |&OID| -> bool) -> Result<(), (~str, GitError)>
+    {
+        let mut callback = callback;
+        let mut stopped = false;
+        let res = {
+            let mut wrapper: |&OID| -> bool = |id| {
+                let go_on = callback(id);
+                if !go_on {
+                    stopped = true;
+                }
+                go_on
+            };
+            unsafe {
+                let payload: *c_void = cast::transmute(&mut wrapper);
+                ext::git_odb_foreach(self.db, cast::transmute(foreach_cb), payload)
+            }
+        };
+        unsafe {
+            if res == 0 || stopped {
+                Ok(())
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Open a stream to read the content of the object `id` piece by piece.
+    ///
+    /// Not every backend can stream reads; the pack backend of libgit2
+    /// cannot. For the objects of such backends, the whole content is read
+    /// into memory and the stream reads from there.
+    pub fn open_rstream<'a>(&'a self, id: &OID) -> Result<OdbReader<'a>, (~str, GitError)>
+    {
+        let mut stream: *ext::git_odb_stream = ptr::null();
+        let mut len: size_t = 0;
+        let mut otype = GIT_OBJ_BAD;
+        let mut obj: *ext::git_odb_object = ptr::null();
+        unsafe {
+            if ext::git_odb_open_rstream(&mut stream, &mut len, &mut otype, self.db, id) == 0 {
+                Ok( OdbReader { stream: stream, obj: ptr::null(), pos: 0, owner: self } )
+            } else if ext::git_odb_read(&mut obj, self.db, id) == 0 {
+                Ok( OdbReader { stream: ptr::null(), obj: obj, pos: 0, owner: self } )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Open a stream to write an object of type `otype`, whose content is
+    /// exactly `size` bytes long.
+    /// The object is stored when `OdbWriter::finalize` is called.
+    pub fn open_wstream<'a>(&'a self, size: uint, otype: OType)
+        -> Result<OdbWriter<'a>, (~str, GitError)>
+    {
+        let mut stream: *ext::git_odb_stream = ptr::null();
+        unsafe {
+            if ext::git_odb_open_wstream(&mut stream, self.db, size as ext::git_object_size_t,
+                                        otype) == 0 {
+                Ok( OdbWriter { stream: stream, owner: self } )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+}
+
+impl Drop for Odb {
+    fn drop(&mut self) {
+        unsafe {
+            ext::git_odb_free(self.db);
+        }
+    }
+}
+
+/// A stream reading the content of an object, see `Odb::open_rstream`
+///
+/// Read errors raise git_error.
+pub struct OdbReader<'a> {
+    priv stream: *ext::git_odb_stream,
+    // the object read whole when the backend cannot stream, and the position in it
+    priv obj: *ext::git_odb_object,
+    priv pos: uint,
+    priv owner: &'a Odb,
+}
+
+impl<'a> Reader for OdbReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> Option<uint>
+    {
+        unsafe {
+            if !self.obj.is_null() {
+                let size = ext::git_odb_object_size(self.obj) as uint;
+                let n = cmp::min(buf.len(), size - self.pos);
+                if n == 0 {
+                    return None;
+                }
+                let data = ext::git_odb_object_data(self.obj) as *u8;
+                vec::raw::buf_as_slice(ptr::offset(data, self.pos as int), n, |chunk| {
+                    vec::bytes::copy_memory(buf, chunk);
+                });
+                self.pos += n;
+                return Some(n);
+            }
+            let res = ext::git_odb_stream_read(self.stream, buf.as_mut_ptr() as *mut c_char,
+                                               buf.len() as size_t);
+            if res < 0 {
+                git_error::cond.raise(last_error());
+                None
+            } else if res == 0 {
+                None
+            } else {
+                Some(res as uint)
+            }
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<'a> Drop for OdbReader<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            if self.obj.is_null() {
+                ext::git_odb_stream_free(self.stream);
+            } else {
+                ext::git_odb_object_free(self.obj);
+            }
+        }
+    }
+}
+
+/// A stream writing a new object, see `Odb::open_wstream`
+///
+/// Write errors raise git_error.
+pub struct OdbWriter<'a> {
+    priv stream: *ext::git_odb_stream,
+    priv owner: &'a Odb,
+}
+
+impl<'a> OdbWriter<'a> {
+    /// Store the object, once all of its content has been written.
+    /// Returns the id of the object.
+    pub fn finalize(self) -> Result<OID, (~str, GitError)>
+    {
+        let mut oid = OID { id: [0, ..20] };
+        unsafe {
+            if ext::git_odb_stream_finalize_write(&mut oid, self.stream) == 0 {
+                Ok(oid)
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+}
+
+impl<'a> Writer for OdbWriter<'a> {
+    fn write(&mut self, buf: &[u8])
+    {
+        unsafe {
+            if ext::git_odb_stream_write(self.stream, buf.as_ptr() as *c_char,
+                                         buf.len() as size_t) != 0 {
+                git_error::cond.raise(last_error());
+            }
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<'a> Drop for OdbWriter<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            ext::git_odb_stream_free(self.stream);
+        }
+    }
+}
+
+impl Repository {
+    /// Get the object database of this repository
+    pub fn odb(&self) -> Result<Odb, (~str, GitError)>
+    {
+        let mut db: *ext::git_odb = ptr::null();
+        unsafe {
+            if ext::git_repository_odb(&mut db, self.repo) == 0 {
+                Ok( Odb { db: db } )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+}
@@ -18,6 +18,12 @@ pub struct Reference<'r> {
     priv owner: &'r Repository,
 }
 
+// the raw pointer is needed by the merge module, but is not part of the public API
+#[doc(hidden)]
+pub fn raw_reference(reference: &Reference) -> *ext::git_reference {
+    reference.c_ref
+}
+
 impl<'r> Reference<'r> {
     pub fn new(c_ref: *ext::git_reference, owner: &'r Repository) -> Reference<'r> {
         Reference {
@@ -26,6 +32,13 @@ impl<'r> Reference<'r> {
         }
     }
 
+    /// Get the full name of a reference, e.g. `refs/heads/master`
+    pub fn name(&self) -> ~str {
+        unsafe {
+            str::raw::from_c_str(ext::git_reference_name(self.c_ref))
+        }
+    }
+
     ///
     /// Return the name of the given local or remote branch.
     ///
@@ -114,6 +127,24 @@ impl<'r> Reference<'r> {
         }
     }
 
+    /// Create a new reference with the same name as the given reference but a
+    /// different OID target. The reference must be a direct reference, otherwise
+    /// this will fail.
+    ///
+    /// The new reference will be written to disk, overwriting the given reference.
+    pub fn set_target(&self, id: &OID) -> Option<Reference<'r>>
+    {
+        let mut ptr: *ext::git_reference = ptr::null();
+        unsafe {
+            if ext::git_reference_set_target(&mut ptr, self.c_ref, id, ptr::null()) == 0 {
+                Some( Reference::new(ptr, self.owner) )
+            } else {
+                raise();
+                None
+            }
+        }
+    }
+
     pub fn resolve(&self) -> OID {
         unsafe {
             let mut resolved_ref: *ext::git_reference = ptr::null();
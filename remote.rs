@@ -0,0 +1,926 @@
+use std::{ptr, vec, str, cast};
+use std::vec::raw::buf_as_slice;
+use std::libc::{c_char, c_int, c_uint, c_void, size_t};
+use ext;
+use super::{OID, GitError, GITERR_INVALID, git_error, last_error};
+use super::{with_opt_c_str, with_c_strarray, from_c_strarray};
+use super::Repository;
+use super::from_c_buf;
+use super::cred::{Cred, CredType, Cert, from_c_credtypes, from_c_cert};
+
+pub use ext::{git_remote_autotag_option_t, GIT_REMOTE_DOWNLOAD_TAGS_UNSPECIFIED,
+              GIT_REMOTE_DOWNLOAD_TAGS_NONE, GIT_REMOTE_DOWNLOAD_TAGS_AUTO,
+              GIT_REMOTE_DOWNLOAD_TAGS_ALL};
+
+/// Progress of downloading and indexing a pack
+pub struct TransferProgress {
+    total_objects: uint,
+    indexed_objects: uint,
+    received_objects: uint,
+    local_objects: uint,
+    total_deltas: uint,
+    indexed_deltas: uint,
+    received_bytes: uint,
+}
+
+unsafe fn from_c_progress(stats: *ext::git_indexer_progress) -> TransferProgress {
+    TransferProgress {
+        total_objects: (*stats).total_objects as uint,
+        indexed_objects: (*stats).indexed_objects as uint,
+        received_objects: (*stats).received_objects as uint,
+        local_objects: (*stats).local_objects as uint,
+        total_deltas: (*stats).total_deltas as uint,
+        indexed_deltas: (*stats).indexed_deltas as uint,
+        received_bytes: (*stats).received_bytes as uint,
+    }
+}
+
+/// Callbacks invoked while talking to a remote.
+///
+/// Every method has a default implementation, so implementors only
+/// override the ones they are interested in. Returning false from a
+/// progress callback cancels the operation.
+pub trait RemoteCallbacks {
+    /// Textual progress from the remote, e.g. "Counting objects: 12".
+    /// The message is passed as sent, since remotes do not promise any encoding.
+    fn sideband_progress(&mut self, _message: &[u8]) -> bool { true }
+
+    /// Progress of downloading and indexing the pack
+    fn transfer_progress(&mut self, _stats: &TransferProgress) -> bool { true }
+
+    /// A reference has been updated. `old` is zero for a newly created reference,
+    /// and `new` is zero for a deleted one.
+    fn update_tips(&mut self, _refname: &str, _old: &OID, _new: &OID) -> bool { true }
+
+    /// Progress of building the pack to push. `current` and `total` count objects.
+    fn pack_progress(&mut self, _stage: PackBuilderStage, _current: uint, _total: uint) -> bool {
+        true
+    }
+
+    /// Progress of sending the pack to the remote
+    fn push_transfer_progress(&mut self, _current: uint, _total: uint, _bytes: uint) -> bool {
+        true
+    }
+
+    /// The remote's answer for a pushed reference.
+    /// `status` is None if the reference was updated, or the rejection message otherwise.
+    fn push_update_reference(&mut self, _refname: &str, _status: Option<&str>) -> bool { true }
+
+    /// The remote asks for credentials. `username` is the one given in the URL, if any,
+    /// and `allowed` lists the kinds of credentials the transport accepts.
+    ///
+    /// This is called again if the credentials are refused, so give up at some point
+    /// by returning None, which lets the transport fall back to its default behavior.
+    fn credentials(&mut self, _url: &str, _username: Option<&str>,
+                   _allowed: &[CredType]) -> Option<Cred> {
+        None
+    }
+
+    /// Decide whether to trust the certificate of `host`. `valid` tells whether the
+    /// transport could verify it on its own; SSH host keys are never verified.
+    ///
+    /// Return None to keep the transport's own decision.
+    fn certificate_check(&mut self, _cert: &Cert, _valid: bool, _host: &str) -> Option<bool> {
+        None
+    }
+}
+
+/// Stages of building a pack
+pub enum PackBuilderStage {
+    GIT_PACKBUILDER_ADDING_OBJECTS = 0,
+    GIT_PACKBUILDER_DELTAFICATION = 1,
+}
+
+/// RemoteCallbacks that do nothing
+pub struct NoCallbacks;
+
+impl RemoteCallbacks for NoCallbacks {}
+
+extern "C" fn sideband_progress_cb(message: *c_char, len: c_int, payload: *c_void) -> c_int
+{
+    unsafe {
+        let callbacks: &mut &mut RemoteCallbacks = cast::transmute(payload);
+        buf_as_slice(message as *u8, len as uint, |message| {
+            if callbacks.sideband_progress(message) { 0 } else { -1 }
+        })
+    }
+}
+
+extern "C" fn transfer_progress_cb(stats: *ext::git_indexer_progress,
+                                   payload: *c_void) -> c_int
+{
+    unsafe {
+        let callbacks: &mut &mut RemoteCallbacks = cast::transmute(payload);
+        if callbacks.transfer_progress(&from_c_progress(stats)) { 0 } else { -1 }
+    }
+}
+
+extern "C" fn update_tips_cb(refname: *c_char, old: *OID, new: *OID,
+                             payload: *c_void) -> c_int
+{
+    unsafe {
+        let callbacks: &mut &mut RemoteCallbacks = cast::transmute(payload);
+        let refname = str::raw::from_c_str(refname);
+        if callbacks.update_tips(refname, &*old, &*new) { 0 } else { -1 }
+    }
+}
+
+extern "C" fn pack_progress_cb(stage: c_int, current: u32, total: u32,
+                               payload: *c_void) -> c_int
+{
+    unsafe {
+        let callbacks: &mut &mut RemoteCallbacks = cast::transmute(payload);
+        let stage = if stage == 0 { GIT_PACKBUILDER_ADDING_OBJECTS }
+                    else { GIT_PACKBUILDER_DELTAFICATION };
+        if callbacks.pack_progress(stage, current as uint, total as uint) { 0 } else { -1 }
+    }
+}
+
+extern "C" fn push_transfer_progress_cb(current: c_uint, total: c_uint, bytes: size_t,
+                                        payload: *c_void) -> c_int
+{
+    unsafe {
+        let callbacks: &mut &mut RemoteCallbacks = cast::transmute(payload);
+        if callbacks.push_transfer_progress(current as uint, total as uint, bytes as uint) {
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+extern "C" fn push_update_reference_cb(refname: *c_char, status: *c_char,
+                                       payload: *c_void) -> c_int
+{
+    unsafe {
+        let callbacks: &mut &mut RemoteCallbacks = cast::transmute(payload);
+        let refname = str::raw::from_c_str(refname);
+        let ok = if status.is_null() {
+            callbacks.push_update_reference(refname, None)
+        } else {
+            let status = str::raw::from_c_str(status);
+            callbacks.push_update_reference(refname, Some(status.as_slice()))
+        };
+        if ok { 0 } else { -1 }
+    }
+}
+
+pub extern "C" fn credentials_cb(out: &mut *ext::git_credential, url: *c_char,
+                                 username_from_url: *c_char, allowed_types: c_uint,
+                                 payload: *c_void) -> c_int
+{
+    unsafe {
+        let callbacks: &mut &mut RemoteCallbacks = cast::transmute(payload);
+        let url = str::raw::from_c_str(url);
+        let username = if username_from_url.is_null() {
+            None
+        } else {
+            Some( str::raw::from_c_str(username_from_url) )
+        };
+        let allowed = from_c_credtypes(allowed_types);
+        match callbacks.credentials(url, username.as_ref().map(|s| s.as_slice()), allowed) {
+            Some(cred) => cred.to_c_cred(out),
+            None => ext::GIT_PASSTHROUGH,
+        }
+    }
+}
+
+extern "C" fn certificate_check_cb(cert: *ext::git_cert, valid: c_int, host: *c_char,
+                                   payload: *c_void) -> c_int
+{
+    unsafe {
+        let callbacks: &mut &mut RemoteCallbacks = cast::transmute(payload);
+        let host = str::raw::from_c_str(host);
+        match callbacks.certificate_check(&from_c_cert(cert), valid != 0, host) {
+            Some(true) => 0,
+            Some(false) => ext::GIT_ECERTIFICATE,
+            None => ext::GIT_PASSTHROUGH,
+        }
+    }
+}
+
+/// Build the C callback table dispatching to `*callbacks`.
+/// `callbacks` must outlive every use of the returned table.
+pub fn to_c_remote_callbacks(callbacks: &mut &mut RemoteCallbacks) -> ext::git_remote_callbacks
+{
+    unsafe {
+        ext::git_remote_callbacks {
+            version: ext::GIT_REMOTE_CALLBACKS_VERSION,
+            sideband_progress: cast::transmute(sideband_progress_cb),
+            completion: ptr::null(),
+            credentials: cast::transmute(credentials_cb),
+            certificate_check: cast::transmute(certificate_check_cb),
+            transfer_progress: cast::transmute(transfer_progress_cb),
+            update_tips: cast::transmute(update_tips_cb),
+            pack_progress: cast::transmute(pack_progress_cb),
+            push_transfer_progress: cast::transmute(push_transfer_progress_cb),
+            push_update_reference: cast::transmute(push_update_reference_cb),
+            push_negotiation: ptr::null(),
+            transport: ptr::null(),
+            remote_ready: ptr::null(),
+            payload: cast::transmute(callbacks),
+            resolve_url: ptr::null(),
+        }
+    }
+}
+
+// no proxy: connect directly
+fn no_c_proxy_options() -> ext::git_proxy_options
+{
+    ext::git_proxy_options {
+        version: ext::GIT_PROXY_OPTIONS_VERSION,
+        proxy_type: ext::GIT_PROXY_NONE,
+        url: ptr::null(),
+        credentials: ptr::null(),
+        certificate_check: ptr::null(),
+        payload: ptr::null(),
+    }
+}
+
+/// Build the C fetch options for `opts`, dispatching callbacks to `*callbacks`.
+/// `callbacks` must outlive every use of the returned options.
+pub fn to_c_fetch_options(opts: &FetchOptions, callbacks: &mut &mut RemoteCallbacks)
+    -> ext::git_fetch_options
+{
+    ext::git_fetch_options {
+        version: ext::GIT_FETCH_OPTIONS_VERSION,
+        callbacks: to_c_remote_callbacks(callbacks),
+        prune: opts.prune as c_uint,
+        update_fetchhead: opts.update_fetchhead as c_int,
+        download_tags: opts.download_tags,
+        proxy_opts: no_c_proxy_options(),
+        custom_headers: ext::git_strarray { strings: ptr::null(), count: 0 },
+    }
+}
+
+/// Whether remote-tracking references that no longer exist on the remote are deleted
+pub enum FetchPrune {
+    /** Use the `fetch.prune` and `remote.<name>.prune` configuration */
+    GIT_FETCH_PRUNE_UNSPECIFIED = 0,
+    /** Force pruning on */
+    GIT_FETCH_PRUNE = 1,
+    /** Force pruning off */
+    GIT_FETCH_NO_PRUNE = 2,
+}
+
+pub struct FetchOptions {
+    prune: FetchPrune,
+    /// Write the fetched references to FETCH_HEAD
+    update_fetchhead: bool,
+    /// Which tags to download, GIT_REMOTE_DOWNLOAD_TAGS_UNSPECIFIED uses the remote's setting
+    download_tags: git_remote_autotag_option_t,
+}
+
+impl FetchOptions {
+    pub fn new() -> FetchOptions {
+        FetchOptions {
+            prune: GIT_FETCH_PRUNE_UNSPECIFIED,
+            update_fetchhead: true,
+            download_tags: GIT_REMOTE_DOWNLOAD_TAGS_UNSPECIFIED,
+        }
+    }
+}
+
+/// Direction of a connection to a remote
+pub enum Direction {
+    GIT_DIRECTION_FETCH = 0,
+    GIT_DIRECTION_PUSH = 1,
+}
+
+/// A reference advertised by a remote
+pub struct RemoteHead {
+    name: ~str,
+    oid: OID,
+    /// The reference a symbolic reference points to, e.g. `refs/heads/master` for `HEAD`
+    symref_target: Option<~str>,
+    /// The OID of the local reference the fetch refspecs map this reference to,
+    /// e.g. `refs/remotes/origin/master`. None if there is no such reference.
+    local_oid: Option<OID>,
+}
+
+// local_oid is left to the caller
+pub unsafe fn from_c_remote_head(head: *ext::git_remote_head) -> RemoteHead {
+    let symref_target = if (*head).symref_target.is_null() {
+        None
+    } else {
+        Some( str::raw::from_c_str((*head).symref_target) )
+    };
+    RemoteHead {
+        name: str::raw::from_c_str((*head).name),
+        oid: (*head).oid,
+        symref_target: symref_target,
+        local_oid: None,
+    }
+}
+
+pub struct PushOptions {
+    /// Number of worker threads used to build the pack, 0 to autodetect
+    pb_parallelism: uint,
+}
+
+impl PushOptions {
+    pub fn new() -> PushOptions {
+        PushOptions {
+            pb_parallelism: 1,
+        }
+    }
+}
+
+// a POSIX extended regular expression matching exactly `s`
+fn exact_regexp(s: &str) -> ~str {
+    let mut regexp = ~"^";
+    for c in s.chars() {
+        if "\\^$.|?*+()[]{}".contains_char(c) {
+            regexp.push_char('\\');
+        }
+        regexp.push_char(c);
+    }
+    regexp.push_char('$');
+    regexp
+}
+
+/// A remote repository, as configured in the `remote.<name>` config section.
+///
+/// The setters of a named remote write its configuration and read it back.
+/// Other remotes loaded with the same name are not updated; look them up again.
+pub struct Remote<'r> {
+    priv remote: *ext::git_remote,
+    priv owner: &'r Repository,
+    // false if libgit2 owns the remote, as for the one handed to a transport factory
+    priv owned: bool,
+}
+
+// a handle on a remote that is not freed with it
+#[doc(hidden)]
+pub fn borrowed_remote<'r>(remote: *ext::git_remote, owner: &'r Repository) -> Remote<'r> {
+    Remote {
+        remote: remote,
+        owner: owner,
+        owned: false,
+    }
+}
+
+impl<'r> Remote<'r> {
+    fn new(remote: *ext::git_remote, owner: &'r Repository) -> Remote<'r> {
+        Remote {
+            remote: remote,
+            owner: owner,
+            owned: true,
+        }
+    }
+
+    /// Get the remote's name
+    ///
+    /// Returns None for an anonymous remote
+    pub fn name(&self) -> Option<~str>
+    {
+        unsafe {
+            let c_name = ext::git_remote_name(self.remote);
+            if c_name.is_null() {
+                None
+            } else {
+                Some( str::raw::from_c_str(c_name) )
+            }
+        }
+    }
+
+    /// Get the remote's url
+    pub fn url(&self) -> ~str
+    {
+        unsafe {
+            str::raw::from_c_str(ext::git_remote_url(self.remote))
+        }
+    }
+
+    /// Get the remote's url for pushing
+    ///
+    /// Returns None if no special url for pushing is set.
+    pub fn pushurl(&self) -> Option<~str>
+    {
+        unsafe {
+            let c_url = ext::git_remote_pushurl(self.remote);
+            if c_url.is_null() {
+                None
+            } else {
+                Some( str::raw::from_c_str(c_url) )
+            }
+        }
+    }
+
+    /// Get the remote's list of fetch refspecs
+    pub fn fetch_refspecs(&self) -> ~[~str]
+    {
+        let mut arr = ext::git_strarray { strings: ptr::null(), count: 0 };
+        unsafe {
+            if ext::git_remote_get_fetch_refspecs(&mut arr, self.remote) == 0 {
+                from_c_strarray(&mut arr)
+            } else {
+                git_error::cond.raise(last_error());
+                ~[]
+            }
+        }
+    }
+
+    /// Get the remote's list of push refspecs
+    pub fn push_refspecs(&self) -> ~[~str]
+    {
+        let mut arr = ext::git_strarray { strings: ptr::null(), count: 0 };
+        unsafe {
+            if ext::git_remote_get_push_refspecs(&mut arr, self.remote) == 0 {
+                from_c_strarray(&mut arr)
+            } else {
+                git_error::cond.raise(last_error());
+                ~[]
+            }
+        }
+    }
+
+    /// Get the tag auto-follow setting of the remote
+    pub fn autotag(&self) -> git_remote_autotag_option_t
+    {
+        unsafe {
+            ext::git_remote_autotag(self.remote)
+        }
+    }
+
+    /// Set the remote's url in the configuration
+    ///
+    /// Like the other setters, this reads the remote back from the configuration,
+    /// which closes its connection, and fails for an anonymous remote.
+    pub fn set_url(&mut self, url: &str) -> Result<(), (~str, GitError)>
+    {
+        let owner = self.owner;
+        self.update(|name| owner.remote_set_url(name, url))
+    }
+
+    /// Set the remote's url for pushing in the configuration.
+    /// Pass None to push to the fetch url.
+    pub fn set_pushurl(&mut self, url: Option<&str>) -> Result<(), (~str, GitError)>
+    {
+        let owner = self.owner;
+        self.update(|name| owner.remote_set_pushurl(name, url))
+    }
+
+    /// Add a fetch refspec to the remote's configuration
+    pub fn add_fetch(&mut self, refspec: &str) -> Result<(), (~str, GitError)>
+    {
+        let owner = self.owner;
+        self.update(|name| owner.remote_add_fetch(name, refspec))
+    }
+
+    /// Add a push refspec to the remote's configuration
+    pub fn add_push(&mut self, refspec: &str) -> Result<(), (~str, GitError)>
+    {
+        let owner = self.owner;
+        self.update(|name| owner.remote_add_push(name, refspec))
+    }
+
+    /// Remove a fetch refspec from the remote's configuration
+    ///
+    /// Returns Err if the remote has no such fetch refspec.
+    pub fn remove_fetch(&mut self, refspec: &str) -> Result<(), (~str, GitError)>
+    {
+        let owner = self.owner;
+        self.update(|name| owner.remote_remove_refspec(name, "fetch", refspec))
+    }
+
+    /// Remove a push refspec from the remote's configuration
+    ///
+    /// Returns Err if the remote has no such push refspec.
+    pub fn remove_push(&mut self, refspec: &str) -> Result<(), (~str, GitError)>
+    {
+        let owner = self.owner;
+        self.update(|name| owner.remote_remove_refspec(name, "push", refspec))
+    }
+
+    /// Set the tag auto-follow setting of the remote in the configuration
+    pub fn set_autotag(&mut self, value: git_remote_autotag_option_t)
+        -> Result<(), (~str, GitError)>
+    {
+        let owner = self.owner;
+        self.update(|name| owner.remote_set_autotag(name, value))
+    }
+
+    // libgit2 only writes remote settings to the configuration,
+    // so look the remote up again for the getters to see them
+    fn update(&mut self, set: |&str| -> Result<(), (~str, GitError)>)
+        -> Result<(), (~str, GitError)>
+    {
+        let name = match self.name() {
+            Some(name) => name,
+            None => return Err( (~"an anonymous remote has no configuration", GITERR_INVALID) ),
+        };
+        match set(name.as_slice()) {
+            Ok(()) => (),
+            Err(e) => return Err(e),
+        }
+        let mut remote: *ext::git_remote = ptr::null();
+        name.with_c_str(|c_name| {
+            unsafe {
+                if ext::git_remote_lookup(&mut remote, self.owner.repo, c_name) == 0 {
+                    if self.owned {
+                        ext::git_remote_free(self.remote);
+                    }
+                    self.remote = remote;
+                    self.owned = true;
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Download new data and update tips
+    ///
+    /// Connects to the remote, downloads the objects the local repository
+    /// lacks, updates the remote-tracking references and FETCH_HEAD, and
+    /// disconnects.
+    ///
+    /// refspecs: the refspecs to use for this fetch. Pass an empty slice
+    ///  to use the base refspecs configured for the remote.
+    ///
+    /// reflog_msg: the message to insert into the reflogs. If None,
+    ///  "fetch <name>" is used, or "fetch <url>" for an anonymous remote.
+    pub fn fetch(&self, refspecs: &[&str], opts: &FetchOptions, callbacks: &mut RemoteCallbacks,
+                 reflog_msg: Option<&str>) -> Result<(), (~str, GitError)>
+    {
+        let mut callbacks = callbacks;
+        let c_opts = to_c_fetch_options(opts, &mut callbacks);
+        with_c_strarray(refspecs, |c_refspecs| {
+            with_opt_c_str(reflog_msg, |c_msg| {
+                unsafe {
+                    if ext::git_remote_fetch(self.remote, c_refspecs, &c_opts, c_msg) == 0 {
+                        Ok(())
+                    } else {
+                        Err( last_error() )
+                    }
+                }
+            })
+        })
+    }
+
+    /// Update the remote with local references and the objects they need
+    ///
+    /// refspecs: the refspecs to push, e.g. `refs/heads/master`,
+    ///  `+refs/heads/topic:refs/heads/topic` to allow a non-fast-forward
+    ///  update, or `:refs/tags/v1.0` to delete a reference on the remote.
+    ///  Pass an empty slice to use the push refspecs configured for the remote.
+    ///
+    /// A push that is not a fast-forward and not forced fails as a whole.
+    /// References the remote refused are not an error: each one is reported
+    /// through `callbacks.push_update_reference` with the remote's message.
+    pub fn push(&self, refspecs: &[&str], opts: &PushOptions, callbacks: &mut RemoteCallbacks)
+        -> Result<(), (~str, GitError)>
+    {
+        let mut callbacks = callbacks;
+        let c_opts = ext::git_push_options {
+            version: ext::GIT_PUSH_OPTIONS_VERSION,
+            pb_parallelism: opts.pb_parallelism as c_uint,
+            callbacks: to_c_remote_callbacks(&mut callbacks),
+            proxy_opts: no_c_proxy_options(),
+            custom_headers: ext::git_strarray { strings: ptr::null(), count: 0 },
+        };
+        with_c_strarray(refspecs, |c_refspecs| {
+            unsafe {
+                if ext::git_remote_push(self.remote, c_refspecs, &c_opts) == 0 {
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Open a connection to the remote
+    ///
+    /// The connection stays open until `disconnect` is called
+    /// or the remote is dropped. `callbacks` are asked for credentials
+    /// and certificate checks while connecting.
+    pub fn connect(&self, direction: Direction, callbacks: &mut RemoteCallbacks)
+        -> Result<(), (~str, GitError)>
+    {
+        let mut callbacks = callbacks;
+        let c_callbacks = to_c_remote_callbacks(&mut callbacks);
+        let proxy_opts = no_c_proxy_options();
+        unsafe {
+            if ext::git_remote_connect(self.remote, direction as ext::git_direction,
+                                       &c_callbacks, &proxy_opts, ptr::null()) == 0 {
+                Ok(())
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Check whether the remote is connected
+    pub fn connected(&self) -> bool
+    {
+        unsafe {
+            ext::git_remote_connected(self.remote) != 0
+        }
+    }
+
+    /// Close the connection to the remote
+    pub fn disconnect(&self)
+    {
+        unsafe {
+            ext::git_remote_disconnect(self.remote);
+        }
+    }
+
+    /// Get the references the remote advertised when connecting
+    ///
+    /// The list stays available after disconnecting; it is an error
+    /// if the remote has never been connected.
+    pub fn list(&self) -> Result<~[RemoteHead], (~str, GitError)>
+    {
+        let mut heads: **ext::git_remote_head = ptr::null();
+        let mut size: size_t = 0;
+        unsafe {
+            if ext::git_remote_ls(&mut heads, &mut size, self.remote) != 0 {
+                return Err( last_error() );
+            }
+            vec::raw::buf_as_slice(heads, size as uint, |slice| {
+                Ok( slice.iter().map(|&head| {
+                    let mut head = from_c_remote_head(head);
+                    let local_oid = self.tracking_ref(head.name.as_slice()).and_then(|refname| {
+                        self.owner.lookup(refname).map(|r| r.resolve())
+                    });
+                    head.local_oid = local_oid;
+                    head
+                }).collect() )
+            })
+        }
+    }
+
+    /// Get the name of the remote's default branch, as advertised by its `HEAD`
+    ///
+    /// The remote must have been connected. Returns None if the remote
+    /// has no `HEAD` or it could not be matched to a branch.
+    pub fn default_branch(&self) -> Option<~str>
+    {
+        let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
+        unsafe {
+            match ext::git_remote_default_branch(&mut buf, self.remote) {
+                0 => Some( from_c_buf(&mut buf) ),
+                ext::GIT_ENOTFOUND => None,
+                _ => {
+                    git_error::cond.raise(last_error());
+                    None
+                },
+            }
+        }
+    }
+
+    // name of the local reference the fetch refspecs map `refname` to
+    fn tracking_ref(&self, refname: &str) -> Option<~str>
+    {
+        refname.with_c_str(|c_name| {
+            unsafe {
+                let count = ext::git_remote_refspec_count(self.remote) as uint;
+                let mut found = None;
+                for n in range(0, count) {
+                    let spec = ext::git_remote_get_refspec(self.remote, n as size_t);
+                    if ext::git_refspec_direction(spec) == ext::GIT_DIRECTION_FETCH &&
+                        ext::git_refspec_src_matches(spec, c_name) != 0 {
+                        let mut buf = ext::git_buf { ptr: ptr::null(), asize: 0, size: 0 };
+                        if ext::git_refspec_transform(&mut buf, spec, c_name) == 0 {
+                            found = Some( from_c_buf(&mut buf) );
+                            break;
+                        }
+                    }
+                }
+                found
+            }
+        })
+    }
+
+    /// Get the statistics of the last download
+    pub fn stats(&self) -> TransferProgress
+    {
+        unsafe {
+            from_c_progress(ext::git_remote_stats(self.remote))
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<'r> Drop for Remote<'r> {
+    fn drop(&mut self) {
+        unsafe {
+            if self.owned {
+                ext::git_remote_free(self.remote);
+            }
+        }
+    }
+}
+
+impl Repository {
+    /// Get the names of the remotes of the repository
+    pub fn remotes(&self) -> Result<~[~str], (~str, GitError)>
+    {
+        let mut arr = ext::git_strarray { strings: ptr::null(), count: 0 };
+        unsafe {
+            if ext::git_remote_list(&mut arr, self.repo) == 0 {
+                Ok( from_c_strarray(&mut arr) )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Get the information for a particular remote
+    ///
+    /// Returns None if no remote named `name` exists, or if the name is invalid
+    pub fn find_remote<'r>(&'r self, name: &str) -> Option<~Remote<'r>>
+    {
+        let mut remote: *ext::git_remote = ptr::null();
+        name.with_c_str(|c_name| {
+            unsafe {
+                match ext::git_remote_lookup(&mut remote, self.repo, c_name) {
+                    0 => Some( ~Remote::new(remote, self) ),
+                    ext::GIT_ENOTFOUND => None,
+                    ext::GIT_EINVALIDSPEC => None,
+                    _ => {
+                        git_error::cond.raise(last_error());
+                        None
+                    },
+                }
+            }
+        })
+    }
+
+    /// Add a remote with the default fetch refspec to the repository's configuration.
+    pub fn remote_create<'r>(&'r self, name: &str, url: &str)
+        -> Result<~Remote<'r>, (~str, GitError)>
+    {
+        let mut remote: *ext::git_remote = ptr::null();
+        name.with_c_str(|c_name| {
+            url.with_c_str(|c_url| {
+                unsafe {
+                    if ext::git_remote_create(&mut remote, self.repo, c_name, c_url) == 0 {
+                        Ok( ~Remote::new(remote, self) )
+                    } else {
+                        Err( last_error() )
+                    }
+                }
+            })
+        })
+    }
+
+    /// Create a remote in memory, without a name and without saving it to the configuration.
+    ///
+    /// Useful when you have a URL instead of a remote's name.
+    /// The remote has no refspecs; pass them to `fetch` or `push`.
+    pub fn remote_create_anonymous<'r>(&'r self, url: &str)
+        -> Result<~Remote<'r>, (~str, GitError)>
+    {
+        let mut remote: *ext::git_remote = ptr::null();
+        url.with_c_str(|c_url| {
+            unsafe {
+                if ext::git_remote_create_anonymous(&mut remote, self.repo, c_url) == 0 {
+                    Ok( ~Remote::new(remote, self) )
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Set the url of the remote `name` in the configuration
+    pub fn remote_set_url(&self, name: &str, url: &str) -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            url.with_c_str(|c_url| {
+                unsafe {
+                    if ext::git_remote_set_url(self.repo, c_name, c_url) == 0 {
+                        Ok(())
+                    } else {
+                        Err( last_error() )
+                    }
+                }
+            })
+        })
+    }
+
+    /// Set the url for pushing of the remote `name` in the configuration.
+    /// Pass None to push to the fetch url.
+    pub fn remote_set_pushurl(&self, name: &str, url: Option<&str>)
+        -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            with_opt_c_str(url, |c_url| {
+                unsafe {
+                    if ext::git_remote_set_pushurl(self.repo, c_name, c_url) == 0 {
+                        Ok(())
+                    } else {
+                        Err( last_error() )
+                    }
+                }
+            })
+        })
+    }
+
+    /// Add a fetch refspec to the remote `name` in the configuration,
+    /// e.g. `+refs/heads/*:refs/remotes/origin/*`
+    pub fn remote_add_fetch(&self, name: &str, refspec: &str) -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            refspec.with_c_str(|c_refspec| {
+                unsafe {
+                    if ext::git_remote_add_fetch(self.repo, c_name, c_refspec) == 0 {
+                        Ok(())
+                    } else {
+                        Err( last_error() )
+                    }
+                }
+            })
+        })
+    }
+
+    /// Add a push refspec to the remote `name` in the configuration,
+    /// e.g. `refs/heads/master:refs/heads/master`
+    pub fn remote_add_push(&self, name: &str, refspec: &str) -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            refspec.with_c_str(|c_refspec| {
+                unsafe {
+                    if ext::git_remote_add_push(self.repo, c_name, c_refspec) == 0 {
+                        Ok(())
+                    } else {
+                        Err( last_error() )
+                    }
+                }
+            })
+        })
+    }
+
+    /// Remove a refspec from the remote `name` in the configuration.
+    /// `direction` is either "fetch" or "push".
+    fn remote_remove_refspec(&self, name: &str, direction: &str, refspec: &str)
+        -> Result<(), (~str, GitError)>
+    {
+        let config = match self.config() {
+            Ok(config) => config,
+            Err(e) => return Err(e),
+        };
+        config.delete_multivar(format!("remote.{}.{}", name, direction), exact_regexp(refspec))
+    }
+
+    /// Set the tag auto-follow setting of the remote `name` in the configuration
+    pub fn remote_set_autotag(&self, name: &str, value: git_remote_autotag_option_t)
+        -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            unsafe {
+                if ext::git_remote_set_autotag(self.repo, c_name, value) == 0 {
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Give a remote a new name
+    ///
+    /// All remote-tracking branches and configuration settings
+    /// for the remote are updated.
+    ///
+    /// Returns the non-default fetch refspecs that could not be renamed;
+    /// they are left unchanged and must be updated by the caller.
+    pub fn remote_rename(&self, name: &str, new_name: &str)
+        -> Result<~[~str], (~str, GitError)>
+    {
+        let mut problems = ext::git_strarray { strings: ptr::null(), count: 0 };
+        name.with_c_str(|c_name| {
+            new_name.with_c_str(|c_new_name| {
+                unsafe {
+                    if ext::git_remote_rename(&mut problems, self.repo, c_name, c_new_name) == 0 {
+                        Ok( from_c_strarray(&mut problems) )
+                    } else {
+                        Err( last_error() )
+                    }
+                }
+            })
+        })
+    }
+
+    /// Delete an existing persisted remote.
+    ///
+    /// All remote-tracking branches and configuration settings
+    /// for the remote will be removed.
+    pub fn remote_delete(&self, name: &str) -> Result<(), (~str, GitError)>
+    {
+        name.with_c_str(|c_name| {
+            unsafe {
+                if ext::git_remote_delete(self.repo, c_name) == 0 {
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+}
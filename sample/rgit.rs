@@ -1,11 +1,21 @@
 extern mod git2;
 use std::os;
+use git2::merge::{MergeOptions, GIT_MERGE_PREFERENCE_NO_FASTFORWARD,
+                  GIT_MERGE_PREFERENCE_FASTFORWARD_ONLY};
+use git2::checkout::CheckoutOptions;
+use git2::remote::{RemoteCallbacks, NoCallbacks, TransferProgress, FetchOptions, PushOptions,
+                   GIT_DIRECTION_FETCH};
 
 fn main_usage(program: &str) {
     println(fmt!("usage: %s <command> [<args>]", program));
     println("   add: Add file contents to the index");
     println("   init: Create an empty git repository");
     println("   clone: Clone a repository into a new directory");
+    println("   ls-remote: List references in a remote repository");
+    println("   fetch: Download objects and refs from another repository");
+    println("   merge: Join two or more development histories together");
+    println("   push: Update remote refs along with associated objects");
+    println("   pull: Fetch from and merge with the upstream of the current branch");
     println("   rm: Remove files from the working tree and from the index");
     println("   status: Show the working tree status");
 }
@@ -35,6 +45,16 @@ fn main() {
         cmd_add(program, cmd_args);
     } else if cmd == ~"rm" {
         cmd_rm(program, cmd_args);
+    } else if cmd == ~"push" {
+        cmd_push(program, cmd_args);
+    } else if cmd == ~"ls-remote" {
+        cmd_ls_remote(program, cmd_args);
+    } else if cmd == ~"fetch" {
+        cmd_fetch(program, cmd_args);
+    } else if cmd == ~"merge" {
+        cmd_merge(program, cmd_args);
+    } else if cmd == ~"pull" {
+        cmd_pull(program, cmd_args);
     } else {
         main_usage(program);
     }
@@ -152,33 +172,241 @@ fn cmd_status(_: &str, _: &[~str]) {
 }
 
 fn add_usage(program: &str) {
-    println(fmt!("usage: %s add <filename>", program));
+    println(fmt!("usage: %s add [-A] [<pathspec>...]", program));
 }
 
 fn cmd_add(program: &str, args: &[~str]) {
-    if args.len() == 0 {
+    let all = args.iter().any(|a| *a == ~"-A" || *a == ~"--all");
+    let pathspecs: ~[&str] = args.iter().filter(|a| !a.starts_with("-"))
+                                        .map(|a| a.as_slice()).collect();
+    if pathspecs.is_empty() && !all {
         add_usage(program);
     } else {
-        let path = args[0];
         let repo = get_current_repo();
         let index = repo.index().unwrap();
-        index.add_bypath(path);
+        index.add_all(pathspecs, [], |_, _| git2::WalkPass).unwrap();
+        if all {
+            // also stage deletions of tracked files
+            index.update_all(pathspecs, |_, _| git2::WalkPass).unwrap();
+        }
         index.write();
     }
 }
 
 fn rm_usage(program: &str) {
-    println(fmt!("usage: %s rm <filename>", program));
+    println(fmt!("usage: %s rm <pathspec>...", program));
 }
 
 fn cmd_rm(program: &str, args: &[~str]) {
     if args.len() == 0 {
         rm_usage(program);
     } else {
-        let path = args[0];
+        let pathspecs: ~[&str] = args.iter().map(|a| a.as_slice()).collect();
         let repo = get_current_repo();
         let index = repo.index().unwrap();
-        index.remove_bypath(path);
+        index.remove_all(pathspecs, |path, _| {
+            println(fmt!("rm '%s'", path));
+            git2::WalkPass
+        }).unwrap();
         index.write();
     }
 }
+
+fn merge_usage(program: &str) {
+    println(fmt!("usage: %s merge <branch>", program));
+}
+
+fn cmd_merge(program: &str, args: &[~str]) {
+    if args.len() == 0 {
+        merge_usage(program);
+        return;
+    }
+
+    let repo = get_current_repo();
+    let branch = match repo.lookup_branch(args[0], false) {
+        Some(b) => b,
+        None => match repo.lookup_branch(args[0], true) {
+            Some(b) => b,
+            None => {
+                println(fmt!("%s - not something we can merge", args[0]));
+                return;
+            }
+        }
+    };
+    merge_ref(&repo, branch);
+}
+
+struct FetchProgress;
+
+impl RemoteCallbacks for FetchProgress {
+    fn transfer_progress(&mut self, stats: &TransferProgress) -> bool {
+        print(fmt!("\rReceiving objects: %u/%u", stats.received_objects, stats.total_objects));
+        true
+    }
+
+    fn update_tips(&mut self, refname: &str, _: &git2::OID, new: &git2::OID) -> bool {
+        println(fmt!("\n%s -> %s", refname, new.to_str()));
+        true
+    }
+}
+
+fn ls_remote_usage(program: &str) {
+    println(fmt!("usage: %s ls-remote [<remote>]", program));
+}
+
+fn cmd_ls_remote(program: &str, args: &[~str]) {
+    if args.len() > 1 {
+        ls_remote_usage(program);
+        return;
+    }
+
+    let name = if args.len() == 0 { "origin" } else { args[0].as_slice() };
+    let repo = get_current_repo();
+    let remote = match repo.find_remote(name) {
+        Some(r) => r,
+        None => {
+            println(fmt!("'%s' does not appear to be a git repository", name));
+            return;
+        }
+    };
+    remote.connect(GIT_DIRECTION_FETCH, &mut NoCallbacks).unwrap();
+    for head in remote.list().unwrap().iter() {
+        println(fmt!("%s\t%s", head.oid.to_str(), head.name));
+    }
+    remote.disconnect();
+}
+
+fn fetch_usage(program: &str) {
+    println(fmt!("usage: %s fetch [<remote>]", program));
+}
+
+fn cmd_fetch(program: &str, args: &[~str]) {
+    if args.len() > 1 {
+        fetch_usage(program);
+        return;
+    }
+
+    let name = if args.len() == 0 { "origin" } else { args[0].as_slice() };
+    let repo = get_current_repo();
+    match repo.find_remote(name) {
+        Some(remote) => {
+            remote.fetch([], &FetchOptions::new(), &mut FetchProgress, None).unwrap();
+        },
+        None => println(fmt!("'%s' does not appear to be a git repository", name)),
+    }
+}
+
+struct PushReport;
+
+impl RemoteCallbacks for PushReport {
+    fn push_update_reference(&mut self, refname: &str, status: Option<&str>) -> bool {
+        match status {
+            None => println(fmt!(" * %s", refname)),
+            Some(msg) => println(fmt!(" ! [remote rejected] %s (%s)", refname, msg)),
+        }
+        true
+    }
+}
+
+fn push_usage(program: &str) {
+    println(fmt!("usage: %s push [<remote> [<refspec>...]]", program));
+}
+
+fn cmd_push(program: &str, args: &[~str]) {
+    if args.len() > 0 && args[0].starts_with("-") {
+        push_usage(program);
+        return;
+    }
+
+    let name = if args.len() == 0 { "origin" } else { args[0].as_slice() };
+    let refspecs: ~[&str] = args.iter().skip(1).map(|s| s.as_slice()).collect();
+    let repo = get_current_repo();
+    match repo.find_remote(name) {
+        Some(remote) => {
+            match remote.push(refspecs, &PushOptions::new(), &mut PushReport) {
+                Ok(()) => (),
+                Err((msg, _)) => println(fmt!("error: failed to push to '%s': %s", name, msg)),
+            }
+        },
+        None => println(fmt!("'%s' does not appear to be a git repository", name)),
+    }
+}
+
+fn pull_usage(program: &str) {
+    println(fmt!("usage: %s pull", program));
+}
+
+fn cmd_pull(program: &str, args: &[~str]) {
+    if args.len() != 0 {
+        pull_usage(program);
+        return;
+    }
+
+    let repo = get_current_repo();
+    let head = match repo.head() {
+        Some(h) => h,
+        None => {
+            println("You are not currently on a branch.");
+            return;
+        }
+    };
+    let upstream_name = match repo.upstream_name(head.name()) {
+        Some(n) => n,
+        None => {
+            println("There is no tracking information for the current branch.");
+            return;
+        }
+    };
+
+    // a local upstream branch has no remote to fetch from
+    match repo.git_branch_remote_name(upstream_name) {
+        Ok(remote_name) => {
+            let remote = repo.find_remote(remote_name).unwrap();
+            remote.fetch([], &FetchOptions::new(), &mut FetchProgress, None).unwrap();
+        },
+        Err(_) => (),
+    }
+
+    match repo.lookup(upstream_name) {
+        Some(upstream) => merge_ref(&repo, upstream),
+        None => println(fmt!("Your configuration specifies to merge with '%s', \
+                              but no such ref was fetched.", upstream_name)),
+    }
+}
+
+fn merge_ref(repo: &git2::Repository, branch: &git2::Reference) {
+    let their_head = repo.annotated_commit_from_ref(branch).unwrap();
+    let target = *their_head.id();
+    let heads = [their_head];
+    let (analysis, preference) = repo.merge_analysis(heads).unwrap();
+    let (no_ff, ff_only) = match preference {
+        GIT_MERGE_PREFERENCE_NO_FASTFORWARD => (true, false),
+        GIT_MERGE_PREFERENCE_FASTFORWARD_ONLY => (false, true),
+        _ => (false, false),
+    };
+
+    if analysis.up_to_date {
+        println("Already up-to-date.");
+    } else if analysis.unborn {
+        println("Cannot merge into a branch with no commits yet.");
+    } else if analysis.fastforward && !no_ff {
+        let commit = repo.lookup_commit(&target).unwrap();
+        repo.checkout_tree(commit.tree(), &CheckoutOptions::new()).unwrap();
+        repo.head().unwrap().set_target(&target);
+        println("Fast-forward");
+    } else if ff_only {
+        println("Not possible to fast-forward, aborting.");
+    } else {
+        repo.merge(heads, &MergeOptions::new(), &CheckoutOptions::new()).unwrap();
+        let index = repo.index().unwrap();
+        if index.has_conflicts() {
+            for conflict in index.conflicts().unwrap() {
+                let entry = if conflict.our.is_some() { conflict.our } else { conflict.their };
+                println(fmt!("CONFLICT (content): Merge conflict in %s", entry.unwrap().path));
+            }
+            println("Automatic merge failed; fix conflicts and then commit the result.");
+        } else {
+            println("Automatic merge went well; stopped before committing as requested");
+        }
+    }
+}
@@ -1,19 +1,24 @@
-use std::libc::c_int;
+use std::libc::{c_int, c_char};
+use std::ptr;
 use std::str::raw::from_c_str;
 use ext;
-use super::{Signature, Time};
+use super::{Signature, Time, GitError, GITERR_INVALID, last_error};
+use super::Repository;
 
-pub fn to_c_sig(sig: &Signature) -> ext::git_signature {
+// the strings of the C signature only live during the call to f
+pub fn with_c_sig<T>(sig: &Signature, f: |&ext::git_signature| -> T) -> T {
     sig.name.with_c_str(|c_name| {
         sig.email.with_c_str(|c_email| {
-            ext::git_signature {
+            let c_sig = ext::git_signature {
                 name: c_name,
                 email: c_email,
                 when: ext::git_time {
                     time: sig.when.time,
                     offset: sig.when.offset as c_int,
+                    sign: if sig.when.offset < 0 { '-' as c_char } else { '+' as c_char },
                 }
-            }
+            };
+            f(&c_sig)
         })
     })
 }
@@ -26,6 +31,392 @@ pub unsafe fn from_c_sig(c_sig: *ext::git_signature) -> Signature {
     }
 }
 
+fn validate(name: &str, email: &str) -> Result<(), (~str, GitError)> {
+    for &(field, value) in [("name", name), ("email", email)].iter() {
+        if value.contains_char('<') || value.contains_char('>') {
+            return Err( (format!("Signature {} '{}' contains angle brackets", field, value),
+                         GITERR_INVALID) );
+        }
+        if value.contains_char('\n') {
+            return Err( (format!("Signature {} '{}' contains a newline", field, value),
+                         GITERR_INVALID) );
+        }
+    }
+    Ok(())
+}
+
+impl Signature {
+    /// Create a new signature.
+    ///
+    /// Fails with GITERR_INVALID if the name or the email
+    /// contains angle brackets or newlines.
+    pub fn new(name: &str, email: &str, when: Time) -> Result<Signature, (~str, GitError)> {
+        validate(name, email).map(|_| {
+            Signature {
+                name: name.to_owned(),
+                email: email.to_owned(),
+                when: when,
+            }
+        })
+    }
+
+    /// Create a new signature with a timestamp of 'now',
+    /// in the local timezone.
+    ///
+    /// Fails with GITERR_INVALID if the name or the email
+    /// contains angle brackets or newlines.
+    pub fn now(name: &str, email: &str) -> Result<Signature, (~str, GitError)> {
+        super::global_init();
+        match validate(name, email) {
+            Err(e) => return Err(e),
+            Ok(()) => (),
+        }
+        let mut c_sig: *ext::git_signature = ptr::null();
+        name.with_c_str(|c_name| {
+            email.with_c_str(|c_email| {
+                unsafe {
+                    if ext::git_signature_now(&mut c_sig, c_name, c_email) == 0 {
+                        let sig = from_c_sig(c_sig);
+                        ext::git_signature_free(c_sig);
+                        Ok(sig)
+                    } else {
+                        Err( last_error() )
+                    }
+                }
+            })
+        })
+    }
+}
+
+impl Repository {
+    /// Create a new signature with a timestamp of 'now', using the
+    /// `user.name` and `user.email` values of the repository configuration.
+    ///
+    /// Fails if either value is not set.
+    pub fn signature(&self) -> Result<Signature, (~str, GitError)> {
+        let mut c_sig: *ext::git_signature = ptr::null();
+        unsafe {
+            if ext::git_signature_default(&mut c_sig, self.repo) == 0 {
+                let sig = from_c_sig(c_sig);
+                ext::git_signature_free(c_sig);
+                Ok(sig)
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+}
+
+static WEEKDAYS: [&'static str, ..7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+static MONTHS: [&'static str, ..12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun",
+                                       "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+// broken-down time in the timezone of a Time
+struct Tm {
+    year: i64,
+    month: i64,     // 1-12
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+    weekday: i64,   // 0 is Sunday
+}
+
+#[inline]
+fn div_floor(a: i64, b: i64) -> i64 {
+    if a >= 0 { a / b } else { (a - b + 1) / b }
+}
+
+// days since 1970-01-01 of a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = div_floor(y, 400);
+    let yoe = y - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = div_floor(z, 146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn to_tm(t: &Time) -> Tm {
+    let local = t.time + (t.offset as i64) * 60;
+    let days = div_floor(local, 86400);
+    let secs = local - days * 86400;
+    let (year, month, day) = civil_from_days(days);
+    Tm {
+        year: year,
+        month: month,
+        day: day,
+        hour: secs / 3600,
+        minute: secs % 3600 / 60,
+        second: secs % 60,
+        weekday: (days % 7 + 11) % 7,
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn from_tm(year: i64, month: i64, day: i64, hour: i64, minute: i64, second: i64,
+           offset: int) -> Option<Time> {
+    if month < 1 || month > 12 || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    if hour < 0 || hour > 23 || minute < 0 || minute > 59 || second < 0 || second > 60 {
+        return None;
+    }
+    let local = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second;
+    Some( Time { time: local - (offset as i64) * 60, offset: offset } )
+}
+
+fn format_offset(offset: int, colon: bool) -> ~str {
+    let sign = if offset < 0 { "-" } else { "+" };
+    let abs = if offset < 0 { -offset } else { offset };
+    let sep = if colon { ":" } else { "" };
+    format!("{}{:02d}{}{:02d}", sign, abs / 60, sep, abs % 60)
+}
+
+// accepts "+0900", "-05:30" and "Z"
+fn parse_offset(s: &str) -> Option<int> {
+    if s == "Z" {
+        return Some(0);
+    }
+    let digits: ~str = s.chars().filter(|&c| c != ':').collect();
+    if digits.len() != 5 || !digits.is_ascii() {
+        return None;
+    }
+    let sign = match digits.char_at(0) {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    match (from_str::<uint>(digits.slice(1, 3)), from_str::<uint>(digits.slice(3, 5))) {
+        (Some(h), Some(m)) if m < 60 => Some(sign * ((h * 60 + m) as int)),
+        _ => None,
+    }
+}
+
+// parses "HH:MM:SS"
+fn parse_clock(s: &str) -> Option<(i64, i64, i64)> {
+    let parts: ~[Option<i64>] = s.split(':').map(|p| from_str::<i64>(p)).collect();
+    match parts {
+        [Some(h), Some(m), Some(s)] => Some((h, m, s)),
+        _ => None,
+    }
+}
+
+fn plural(n: i64, unit: &str) -> ~str {
+    if n == 1 {
+        format!("{} {}", n, unit)
+    } else {
+        format!("{} {}s", n, unit)
+    }
+}
+
+impl Time {
+    /// Format as git's raw format, e.g. `1371000000 +0900`
+    pub fn to_raw(&self) -> ~str {
+        format!("{} {}", self.time, format_offset(self.offset, false))
+    }
+
+    /// Format as RFC 2822, e.g. `Wed, 12 Jun 2013 10:20:00 +0900`.
+    /// This matches `git log --date=rfc2822`.
+    pub fn to_rfc2822(&self) -> ~str {
+        let tm = to_tm(self);
+        format!("{}, {} {} {} {:02d}:{:02d}:{:02d} {}", WEEKDAYS[tm.weekday], tm.day,
+                MONTHS[tm.month - 1], tm.year, tm.hour, tm.minute, tm.second,
+                format_offset(self.offset, false))
+    }
+
+    /// Format as ISO 8601-like, e.g. `2013-06-12 10:20:00 +0900`.
+    /// This matches `git log --date=iso`.
+    pub fn to_iso8601(&self) -> ~str {
+        let tm = to_tm(self);
+        format!("{:04d}-{:02d}-{:02d} {:02d}:{:02d}:{:02d} {}", tm.year, tm.month, tm.day,
+                tm.hour, tm.minute, tm.second, format_offset(self.offset, false))
+    }
+
+    /// Format as strict ISO 8601, e.g. `2013-06-12T10:20:00+09:00`.
+    /// This matches `git log --date=iso-strict`.
+    pub fn to_iso8601_strict(&self) -> ~str {
+        let tm = to_tm(self);
+        format!("{:04d}-{:02d}-{:02d}T{:02d}:{:02d}:{:02d}{}", tm.year, tm.month, tm.day,
+                tm.hour, tm.minute, tm.second, format_offset(self.offset, true))
+    }
+
+    /// Format relative to `now` (seconds from epoch), e.g. `3 days ago`.
+    /// This matches `git log --date=relative`.
+    pub fn to_relative(&self, now: i64) -> ~str {
+        let mut diff = now - self.time;
+        if diff < 0 {
+            return ~"in the future";
+        }
+        if diff < 90 {
+            return plural(diff, "second") + " ago";
+        }
+        diff = (diff + 30) / 60;
+        if diff < 90 {
+            return plural(diff, "minute") + " ago";
+        }
+        diff = (diff + 30) / 60;
+        if diff < 36 {
+            return plural(diff, "hour") + " ago";
+        }
+        diff = (diff + 12) / 24;
+        if diff < 14 {
+            return plural(diff, "day") + " ago";
+        }
+        if diff < 70 {
+            return plural((diff + 3) / 7, "week") + " ago";
+        }
+        if diff < 365 {
+            return plural((diff + 15) / 30, "month") + " ago";
+        }
+        if diff < 1825 {
+            let total_months = (diff * 12 * 2 + 365) / (365 * 2);
+            let years = plural(total_months / 12, "year");
+            let months = total_months % 12;
+            if months == 0 {
+                return years + " ago";
+            } else {
+                return format!("{}, {} ago", years, plural(months, "month"));
+            }
+        }
+        plural((diff + 183) / 365, "year") + " ago"
+    }
+
+    /// Parse git's raw format, e.g. `1371000000 +0900`
+    pub fn from_raw(s: &str) -> Option<Time> {
+        let words: ~[&str] = s.words().collect();
+        match words {
+            [time, offset] => match (from_str::<i64>(time), parse_offset(offset)) {
+                (Some(t), Some(o)) => Some( Time { time: t, offset: o } ),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Parse RFC 2822, e.g. `Wed, 12 Jun 2013 10:20:00 +0900`.
+    /// The day of the week is optional and not checked.
+    pub fn from_rfc2822(s: &str) -> Option<Time> {
+        let mut words: ~[&str] = s.words().collect();
+        if words.len() == 6 && words[0].ends_with(",") {
+            words.shift();
+        }
+        match words {
+            [day, month, year, clock, offset] => {
+                let month = match MONTHS.iter().position(|m| m.eq_ignore_ascii_case(month)) {
+                    Some(i) => (i + 1) as i64,
+                    None => return None,
+                };
+                match (from_str::<i64>(day), from_str::<i64>(year), parse_clock(clock),
+                       parse_offset(offset)) {
+                    (Some(d), Some(y), Some((h, mi, sec)), Some(o)) =>
+                        from_tm(y, month, d, h, mi, sec, o),
+                    _ => None,
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// Parse ISO 8601, either as formatted by `to_iso8601`
+    /// (`2013-06-12 10:20:00 +0900`) or strictly (`2013-06-12T10:20:00+09:00`).
+    pub fn from_iso8601(s: &str) -> Option<Time> {
+        let s = s.trim();
+        if !s.is_ascii() || s.len() < 20 {
+            return None;
+        }
+        let date = s.slice(0, 10);
+        let sep = s.char_at(10);
+        let clock = s.slice(11, 19);
+        let offset = s.slice_from(19).trim();
+        if sep != ' ' && sep != 'T' {
+            return None;
+        }
+        let ymd: ~[Option<i64>] = date.split('-').map(|p| from_str::<i64>(p)).collect();
+        match (ymd, parse_clock(clock), parse_offset(offset)) {
+            ([Some(y), Some(m), Some(d)], Some((h, mi, sec)), Some(o)) =>
+                from_tm(y, m, d, h, mi, sec, o),
+            _ => None,
+        }
+    }
+
+    /// Parse a relative time such as `3 days ago` against `now` (seconds from epoch).
+    ///
+    /// Months are counted as 30 days and years as 365 days, so this is the
+    /// inverse of `to_relative` only up to its rounding. The result is in UTC.
+    pub fn from_relative(s: &str, now: i64) -> Option<Time> {
+        let words: ~[&str] = s.words().collect();
+        if words.len() < 3 || words.len() % 2 != 1 || words[words.len() - 1] != "ago" {
+            return None;
+        }
+        let mut total = 0i64;
+        for pair in words.slice_to(words.len() - 1).chunks(2) {
+            let n = match from_str::<i64>(pair[0]) {
+                Some(n) => n,
+                None => return None,
+            };
+            let unit = pair[1].trim_right_chars(',');
+            let unit = if unit.ends_with("s") { unit.slice_to(unit.len() - 1) } else { unit };
+            let secs = match unit {
+                "second" => 1,
+                "minute" => 60,
+                "hour" => 60 * 60,
+                "day" => 24 * 60 * 60,
+                "week" => 7 * 24 * 60 * 60,
+                "month" => 30 * 24 * 60 * 60,
+                "year" => 365 * 24 * 60 * 60,
+                _ => return None,
+            };
+            total += n * secs;
+        }
+        Some( Time { time: now - total, offset: 0 } )
+    }
+}
+
+impl Signature {
+    /// Format as a git ident line, e.g. `Name <email> 1371000000 +0900`
+    pub fn to_ident(&self) -> ~str {
+        format!("{} <{}> {}", self.name, self.email, self.when.to_raw())
+    }
+
+    /// Parse a git ident line, e.g. `Name <email> 1371000000 +0900`,
+    /// as found in the author and committer headers of a raw commit.
+    pub fn from_ident(s: &str) -> Option<Signature> {
+        let (lt, gt) = match (s.find('<'), s.rfind('>')) {
+            (Some(lt), Some(gt)) if lt < gt => (lt, gt),
+            _ => return None,
+        };
+        let name = s.slice_to(lt).trim();
+        let email = s.slice(lt + 1, gt);
+        match Time::from_raw(s.slice_from(gt + 1)) {
+            Some(when) => Signature::new(name, email, when).ok(),
+            None => None,
+        }
+    }
+}
+
 #[inline]
 fn time_cmp(a: &Time, b: &Time) -> i64 {
     let a_utc = a.time + (a.offset as i64) * 60;
@@ -0,0 +1,175 @@
+use std::{ptr, vec, str, cast};
+use std::libc::{c_char, c_int, c_uint, c_void, size_t};
+use ext;
+use signature;
+use super::{OID, Signature, GitError, last_error, with_opt_c_str};
+use super::Repository;
+use super::checkout::{CheckoutOptions, with_c_checkout_opts};
+
+pub enum StashFlag {
+    /** All changes already added to the index are left intact in the working directory */
+    GIT_STASH_KEEP_INDEX = (1 << 0),
+    /** All untracked files are also stashed and then cleaned up from the working directory */
+    GIT_STASH_INCLUDE_UNTRACKED = (1 << 1),
+    /** All ignored files are also stashed and then cleaned up from the working directory */
+    GIT_STASH_INCLUDE_IGNORED = (1 << 2),
+}
+
+pub enum StashApplyFlag {
+    /** Try to reinstate not only the working tree's changes,
+     *  but also the index's changes */
+    GIT_STASH_APPLY_REINSTATE_INDEX = (1 << 0),
+}
+
+pub struct StashApplyOptions {
+    flags: ~[StashApplyFlag],
+    checkout: CheckoutOptions,
+}
+
+impl StashApplyOptions {
+    pub fn new() -> StashApplyOptions {
+        StashApplyOptions {
+            flags: ~[],
+            checkout: CheckoutOptions::new(),
+        }
+    }
+}
+
+/// A stashed state
+pub struct StashEntry {
+    /// Position of the stash in the stash list. 0 is the most recent stash
+    index: uint,
+    message: ~str,
+    /// Id of the commit holding the stashed state
+    id: OID,
+}
+
+extern "C" fn stash_cb(index: size_t, message: *c_char, stash_id: *OID,
+                       payload: *c_void) -> c_int
+{
+    unsafe {
+        let entries: &mut ~[StashEntry] = cast::transmute(payload);
+        entries.push(StashEntry {
+            index: index as uint,
+            message: str::raw::from_c_str(message),
+            id: *stash_id,
+        });
+        0
+    }
+}
+
+fn with_c_stash_apply_opts<T>(opts: &StashApplyOptions,
+                              f: |&ext::git_stash_apply_options| -> T) -> T
+{
+    with_c_checkout_opts(&opts.checkout, |c_checkout_opts| {
+        let c_opts = ext::git_stash_apply_options {
+            version: ext::GIT_STASH_APPLY_OPTIONS_VERSION,
+            flags: opts.flags.iter().fold(0, |acc, &f| acc | (f as u32)),
+            checkout_options: *c_checkout_opts,
+            progress_cb: ptr::null(),
+            progress_payload: ptr::null(),
+        };
+        f(&c_opts)
+    })
+}
+
+impl Repository {
+    /// Save the local modifications to a new stash.
+    ///
+    /// stasher: The identity of the person performing the stashing.
+    ///
+    /// message: Optional description along with the stashed state.
+    ///
+    /// Returns the id of the commit containing the stashed state.
+    /// Fails with the GITERR_STASH class if there is nothing to stash.
+    pub fn stash_save(&self, stasher: &Signature, message: Option<&str>, flags: &[StashFlag])
+        -> Result<OID, (~str, GitError)>
+    {
+        let c_flags = flags.iter().fold(0, |acc, &f| acc | (f as c_uint));
+        let mut oid = OID { id: [0, ..20] };
+        signature::with_c_sig(stasher, |c_stasher| {
+            with_opt_c_str(message, |c_message| {
+                unsafe {
+                    if ext::git_stash_save(&mut oid, self.repo, c_stasher, c_message,
+                                           c_flags) == 0 {
+                        Ok(oid)
+                    } else {
+                        Err( last_error() )
+                    }
+                }
+            })
+        })
+    }
+
+    /// Iterate over all the stashed states, from the most recent to the oldest
+    pub fn stashes(&self) -> Result<vec::MoveIterator<StashEntry>, (~str, GitError)>
+    {
+        let mut entries: ~[StashEntry] = ~[];
+        unsafe {
+            let payload: *c_void = cast::transmute(&mut entries);
+            if ext::git_stash_foreach(self.repo, cast::transmute(stash_cb), payload) == 0 {
+                Ok( entries.move_iter() )
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Apply a single stashed state from the stash list.
+    ///
+    /// If local changes in the working directory conflict with changes in the
+    /// stash then this will fail with a GITERR_STASH class. In this case, the
+    /// index will always remain unmodified and all files in the working directory
+    /// will remain unmodified. However, if you are restoring untracked files or
+    /// ignored files and there is a conflict when applying the modified files,
+    /// then those files will remain in the working directory.
+    ///
+    /// If the GIT_STASH_APPLY_REINSTATE_INDEX flag is set and it would not be
+    /// possible to restore the index, then the stash is not applied and an
+    /// error is returned.
+    ///
+    /// index: The position within the stash list. 0 points to the most recent stashed state.
+    pub fn stash_apply(&self, index: uint, opts: &StashApplyOptions)
+        -> Result<(), (~str, GitError)>
+    {
+        with_c_stash_apply_opts(opts, |c_opts| {
+            unsafe {
+                if ext::git_stash_apply(self.repo, index as size_t, c_opts) == 0 {
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Apply a single stashed state from the stash list and remove it from the list
+    /// if successful.
+    pub fn stash_pop(&self, index: uint, opts: &StashApplyOptions)
+        -> Result<(), (~str, GitError)>
+    {
+        with_c_stash_apply_opts(opts, |c_opts| {
+            unsafe {
+                if ext::git_stash_pop(self.repo, index as size_t, c_opts) == 0 {
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    }
+
+    /// Remove a single stashed state from the stash list.
+    ///
+    /// index: The position within the stash list. 0 points to the most recent stashed state.
+    pub fn stash_drop(&self, index: uint) -> Result<(), (~str, GitError)>
+    {
+        unsafe {
+            if ext::git_stash_drop(self.repo, index as size_t) == 0 {
+                Ok(())
+            } else {
+                Err( last_error() )
+            }
+        }
+    }
+}
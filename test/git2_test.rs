@@ -1,6 +1,102 @@
 extern mod extra;
 extern mod git2;
 
+use std::io::File;
+use std::io::fs;
+use std::io::mem::MemReader;
+use std::io::{Listener, Acceptor};
+use std::io::net::ip::{SocketAddr, Ipv4Addr};
+use std::io::net::tcp::TcpListener;
+use std::{run, str};
+use extra::tempfile::TempDir;
+use git2::git_index::{IndexEntry, GIT_INDEX_ADD_FORCE, GIT_INDEX_ADD_CHECK_PATHSPEC};
+use git2::merge::MergeOptions;
+use git2::checkout::CheckoutOptions;
+use git2::stash::{StashEntry, StashApplyOptions};
+use git2::Config;
+use git2::config::GIT_CONFIG_LEVEL_LOCAL;
+use git2::cred::{Cred, CredType, CredUserPassPlaintext, GIT_CREDTYPE_USERPASS_PLAINTEXT,
+                 Cert, CertX509};
+use git2::filter::{FilterList, GIT_FILTER_TO_ODB, GIT_FILTER_TO_WORKTREE, Filter, FilterSource,
+                   GIT_FILTER_DRIVER_PRIORITY, filter_register, filter_unregister};
+use git2::tree::{GIT_TREEWALK_PRE, GIT_TREEWALK_POST, TreeUpdater};
+use git2::transport::{Transport, TransportFactory, transport_register, transport_unregister};
+use git2::transport::{SmartSubtransport, SmartSubtransportFactory, SmartSubtransportStream,
+                      SmartService,
+                      smart_subtransport_register, GIT_SERVICE_UPLOADPACK_LS,
+                      GIT_SERVICE_UPLOADPACK, GIT_SERVICE_RECEIVEPACK_LS};
+use git2::remote::{RemoteCallbacks, NoCallbacks, FetchOptions, GIT_FETCH_PRUNE, PushOptions,
+                   GIT_DIRECTION_FETCH, Direction, RemoteHead, TransferProgress,
+                   PackBuilderStage, GIT_PACKBUILDER_ADDING_OBJECTS};
+
+fn write_file(dir: &Path, name: &str, content: &str) {
+    let mut file = File::create(&dir.join(name)).unwrap();
+    file.write(content.as_bytes());
+}
+
+// a new repository in a temporary directory, removed along with the TempDir
+fn temp_repo(bare: bool) -> (TempDir, git2::Repository) {
+    let dir = TempDir::new("git2_test").unwrap();
+    let repo = git2::Repository::init(dir.path().as_str().unwrap(), bare).unwrap();
+    (dir, repo)
+}
+
+fn sample_sig() -> git2::Signature {
+    git2::Signature {
+        name: ~"git2-rs",
+        email: ~"git2-rs@example.com",
+        when: git2::Time { time: 1371000000, offset: 9 * 60 },
+    }
+}
+
+fn read_file(dir: &Path, name: &str) -> ~str {
+    File::open(&dir.join(name)).unwrap().read_to_str()
+}
+
+// a tree holding a.txt, dir/b.txt and dir/sub/c.txt
+fn nested_tree<'r>(repo: &'r git2::Repository) -> ~git2::Tree<'r> {
+    let blob = repo.blob_create_frombuffer("x\n".as_bytes()).unwrap();
+    let sub = git2::tree::TreeBuilder::new(repo).unwrap();
+    sub.insert("c.txt", blob.id(), git2::GIT_FILEMODE_BLOB).unwrap();
+    let dir = git2::tree::TreeBuilder::new(repo).unwrap();
+    dir.insert("b.txt", blob.id(), git2::GIT_FILEMODE_BLOB).unwrap();
+    dir.insert("sub", &sub.write().unwrap(), git2::GIT_FILEMODE_TREE).unwrap();
+    let root = git2::tree::TreeBuilder::new(repo).unwrap();
+    root.insert("a.txt", blob.id(), git2::GIT_FILEMODE_BLOB).unwrap();
+    root.insert("dir", &dir.write().unwrap(), git2::GIT_FILEMODE_TREE).unwrap();
+    repo.lookup_tree(&root.write().unwrap()).unwrap()
+}
+
+// the paths of the files in a tree, depth first
+fn tree_paths(tree: &git2::Tree) -> ~[~str] {
+    tree.paths().map(|p| p.unwrap()).collect()
+}
+
+// commits a tree holding a single file, without parents.
+// The same commit comes out in any repository.
+fn commit_blob(repo: &git2::Repository, update_ref: Option<&str>, name: &str, text: &str)
+    -> git2::OID {
+    let blob = repo.blob_create_frombuffer(text.as_bytes()).unwrap();
+    let builder = git2::tree::TreeBuilder::new(repo).unwrap();
+    builder.insert(name, blob.id(), git2::GIT_FILEMODE_BLOB).unwrap();
+    let tree = repo.lookup_tree(&builder.write().unwrap()).unwrap();
+    let sig = sample_sig();
+    repo.commit(update_ref, &sig, &sig, None, name, tree, [])
+}
+
+fn force_checkout(repo: &git2::Repository, tree: &git2::Tree) {
+    let mut opts = CheckoutOptions::new();
+    opts.strategy = ~[git2::checkout::GIT_CHECKOUT_FORCE];
+    repo.checkout_tree(tree, &opts).unwrap();
+}
+
+// commits a single file like commit_blob, and checks it out in the index and working directory
+fn checkout_blob<'r>(repo: &'r git2::Repository, name: &str, text: &str) -> ~git2::Commit<'r> {
+    let commit = repo.lookup_commit(&commit_blob(repo, Some("HEAD"), name, text)).unwrap();
+    force_checkout(repo, commit.tree());
+    commit
+}
+
 #[test]
 fn repo_head() {
     let repo = git2::Repository::open("fixture").unwrap();
@@ -112,9 +208,9 @@ fn commit() {
     let text = "blob text\n";
     let blob = repo.blob_create_frombuffer(text.as_bytes()).unwrap();
 
-    let treebuilder = git2::tree::TreeBuilder::from_tree(parent.tree());
-    treebuilder.insert(&"test_blob.txt", blob.id(), git2::GIT_FILEMODE_BLOB);
-    let tree_id = treebuilder.write(&repo);
+    let treebuilder = git2::tree::TreeBuilder::from_tree(parent.tree()).unwrap();
+    treebuilder.insert(&"test_blob.txt", blob.id(), git2::GIT_FILEMODE_BLOB).unwrap();
+    let tree_id = treebuilder.write().unwrap();
     let tree = match repo.lookup_tree(&tree_id) {
         None => fail!(~"tree does not exist"),
         Some(t) => t,
@@ -129,3 +225,1534 @@ fn commit() {
         }
     };
 }
+
+#[test]
+fn index_add_all() {
+    let (dir, repo) = temp_repo(false);
+    write_file(dir.path(), ".gitignore", "*.log\n");
+    write_file(dir.path(), "a.txt", "a\n");
+    write_file(dir.path(), "b.log", "b\n");
+
+    let index = repo.index().unwrap();
+    let mut added: ~[~str] = ~[];
+    index.add_all([], [], |path, _| {
+        added.push(path.to_owned());
+        git2::WalkPass
+    }).unwrap();
+    assert_eq!(added, ~[~".gitignore", ~"a.txt"]);
+
+    let mut removed: ~[~str] = ~[];
+    index.remove_all(["*.txt"], |path, _| {
+        removed.push(path.to_owned());
+        git2::WalkPass
+    }).unwrap();
+    assert_eq!(removed, ~[~"a.txt"]);
+
+    // stopping is not an error, and the stopped file is not added
+    let mut added: ~[~str] = ~[];
+    index.add_all(["*.txt"], [], |path, _| {
+        added.push(path.to_owned());
+        git2::WalkStop
+    }).unwrap();
+    assert_eq!(added, ~[~"a.txt"]);
+    assert_eq!(tree_paths(index.write_tree().unwrap()), ~[~".gitignore"]);
+
+    // an ignored file named exactly is skipped, refused with CHECK_PATHSPEC,
+    // and added with FORCE
+    index.add_all(["b.log"], [], |_, _| git2::WalkPass).unwrap();
+    assert_eq!(tree_paths(index.write_tree().unwrap()), ~[~".gitignore"]);
+    assert!(index.add_all(["b.log"], [GIT_INDEX_ADD_CHECK_PATHSPEC],
+                          |_, _| git2::WalkPass).is_err());
+    index.add_all(["b.log"], [GIT_INDEX_ADD_FORCE], |_, _| git2::WalkPass).unwrap();
+    assert_eq!(tree_paths(index.write_tree().unwrap()), ~[~".gitignore", ~"b.log"]);
+}
+
+#[test]
+fn index_update_all() {
+    let (dir, repo) = temp_repo(false);
+    write_file(dir.path(), "a.txt", "a\n");
+    write_file(dir.path(), "b.txt", "b\n");
+    write_file(dir.path(), "c.txt", "c\n");
+    let index = repo.index().unwrap();
+    index.add_all([], [], |_, _| git2::WalkPass).unwrap();
+
+    // tracked files are re-read or dropped, untracked ones are not added
+    write_file(dir.path(), "a.txt", "changed\n");
+    fs::unlink(&dir.path().join("b.txt"));
+    write_file(dir.path(), "d.txt", "d\n");
+    let mut updated: ~[~str] = ~[];
+    index.update_all([], |path, _| {
+        updated.push(path.to_owned());
+        git2::WalkPass
+    }).unwrap();
+    assert_eq!(updated, ~[~"a.txt", ~"b.txt"]);
+    assert_eq!(tree_paths(index.write_tree().unwrap()), ~[~"a.txt", ~"c.txt"]);
+    let changed = repo.blob_create_frombuffer("changed\n".as_bytes()).unwrap();
+    assert_eq!(index.write_tree().unwrap().entry_byname("a.txt").unwrap().id(), changed.id());
+
+    // only the entries matching the pathspecs are updated
+    write_file(dir.path(), "a.txt", "again\n");
+    write_file(dir.path(), "c.txt", "again\n");
+    index.update_all(["c.txt"], |_, _| git2::WalkPass).unwrap();
+    let tree = index.write_tree().unwrap();
+    assert_eq!(tree.entry_byname("a.txt").unwrap().id(), changed.id());
+    assert!(tree.entry_byname("c.txt").unwrap().id() != changed.id());
+}
+
+#[test]
+fn index_conflicts() {
+    let (_dir, repo) = temp_repo(false);
+    let ancestor = repo.blob_create_frombuffer("base\n".as_bytes()).unwrap();
+    let our = repo.blob_create_frombuffer("ours\n".as_bytes()).unwrap();
+    let their = repo.blob_create_frombuffer("theirs\n".as_bytes()).unwrap();
+
+    let index = repo.index().unwrap();
+    assert!(!index.has_conflicts());
+    index.conflict_add(
+        Some(&IndexEntry::new("file.txt", ancestor.id(), git2::GIT_FILEMODE_BLOB)),
+        Some(&IndexEntry::new("file.txt", our.id(), git2::GIT_FILEMODE_BLOB)),
+        Some(&IndexEntry::new("file.txt", their.id(), git2::GIT_FILEMODE_BLOB)));
+    assert!(index.has_conflicts());
+
+    let conflicts: ~[git2::git_index::IndexConflict] = index.conflicts().unwrap().collect();
+    assert_eq!(conflicts.len(), 1);
+    let our_entry = conflicts[0].our.get_ref();
+    assert_eq!(our_entry.path, ~"file.txt");
+    assert_eq!(&our_entry.oid, our.id());
+    assert_eq!(our_entry.stage(), 2);
+
+    match index.write_tree() {
+        Err(git2::git_index::Unmerged(_)) => (),
+        _ => fail!(~"wrote a tree from a conflicted index"),
+    }
+
+    index.conflict_cleanup();
+    assert!(!index.has_conflicts());
+    assert!(index.conflict_get("file.txt").is_none());
+}
+
+#[test]
+fn graph_ahead_behind() {
+    let repo = git2::Repository::open("fixture").unwrap();
+    let parent_id: git2::OID = from_str(&"21002f5d3f411fe990e13604273a51cd598a4a51").unwrap();
+    let base = repo.lookup_commit(&parent_id).unwrap();
+    let tree = base.tree();
+    let sig = base.author();
+    let parent = repo.lookup_commit(&parent_id).unwrap();
+    let child_id = repo.commit(None, &sig, &sig, None, "child", tree, [parent]);
+
+    assert_eq!(repo.merge_base(&child_id, &parent_id), Some(parent_id));
+    assert_eq!(repo.merge_bases_many([child_id, parent_id]), ~[parent_id]);
+    assert_eq!(repo.graph_ahead_behind(&child_id, &parent_id).unwrap(), (1, 0));
+    assert_eq!(repo.graph_ahead_behind(&parent_id, &child_id).unwrap(), (0, 1));
+    assert!(repo.graph_descendant_of(&child_id, &parent_id));
+    assert!(!repo.graph_descendant_of(&parent_id, &child_id));
+}
+
+#[test]
+fn graph_diverged() {
+    let (_dir, repo) = temp_repo(true);
+    let sig = sample_sig();
+    let child = |parent_id: &git2::OID, message: &str| {
+        let tree_of = repo.lookup_commit(parent_id).unwrap();
+        let parent = repo.lookup_commit(parent_id).unwrap();
+        repo.commit(None, &sig, &sig, None, message, tree_of.tree(), [parent])
+    };
+
+    // base -> ours1 -> ours2 -> ours3, and base -> theirs1 -> theirs2
+    let base = commit_blob(&repo, None, "a.txt", "base\n");
+    let ours1 = child(&base, "ours 1");
+    let ours3 = child(&child(&ours1, "ours 2"), "ours 3");
+    let theirs2 = child(&child(&base, "theirs 1"), "theirs 2");
+    let side = child(&ours1, "side");
+
+    assert_eq!(repo.graph_ahead_behind(&ours3, &theirs2).unwrap(), (3, 2));
+    assert_eq!(repo.graph_ahead_behind(&theirs2, &ours3).unwrap(), (2, 3));
+    assert_eq!(repo.merge_base(&ours3, &theirs2), Some(base));
+    assert_eq!(repo.merge_base(&ours3, &side), Some(ours1));
+    assert_eq!(repo.merge_bases_many([ours3, theirs2]), ~[base]);
+    assert_eq!(repo.merge_base_octopus([ours3, theirs2, side]), Some(base));
+    assert!(!repo.graph_descendant_of(&ours3, &theirs2));
+
+    // a second root shares no history with the others
+    let root = commit_blob(&repo, None, "b.txt", "root\n");
+    assert_eq!(repo.graph_ahead_behind(&ours3, &root).unwrap(), (4, 1));
+    assert_eq!(repo.merge_base(&ours3, &root), None);
+    assert_eq!(repo.merge_bases_many([ours3, root]), ~[]);
+    assert_eq!(repo.merge_base_octopus([ours3, theirs2, root]), None);
+    assert!(!repo.graph_descendant_of(&ours3, &root));
+}
+
+#[test]
+fn merge_trees() {
+    let repo = git2::Repository::open("fixture").unwrap();
+    let base_id = from_str(&"21002f5d3f411fe990e13604273a51cd598a4a51").unwrap();
+    let base = repo.lookup_commit(&base_id).unwrap();
+    let base_tree = base.tree();
+
+    let add_blob = |name: &str, text: &str| {
+        let blob = repo.blob_create_frombuffer(text.as_bytes()).unwrap();
+        let builder = git2::tree::TreeBuilder::from_tree(base_tree).unwrap();
+        builder.insert(name, blob.id(), git2::GIT_FILEMODE_BLOB).unwrap();
+        repo.lookup_tree(&builder.write().unwrap()).unwrap()
+    };
+    let ours = add_blob("ours.txt", "ours\n");
+    let theirs = add_blob("theirs.txt", "theirs\n");
+    let conflicting = add_blob("ours.txt", "conflict\n");
+
+    let opts = MergeOptions::new();
+    let clean = repo.merge_trees(Some(base_tree), ours, theirs, &opts).unwrap();
+    assert!(!clean.has_conflicts());
+    let merged = clean.write_tree().unwrap();
+    assert!(merged.entry_byname("ours.txt").is_some());
+    assert!(merged.entry_byname("theirs.txt").is_some());
+
+    let conflicted = repo.merge_trees(Some(base_tree), ours, conflicting, &opts).unwrap();
+    assert!(conflicted.has_conflicts());
+    assert!(conflicted.conflict_get("ours.txt").is_some());
+
+    let mut favor_ours = MergeOptions::new();
+    favor_ours.file_favor = git2::merge::GIT_MERGE_FILE_FAVOR_OURS;
+    let resolved = repo.merge_trees(Some(base_tree), ours, conflicting, &favor_ours).unwrap();
+    assert!(!resolved.has_conflicts());
+}
+
+#[test]
+fn merge_analysis() {
+    let (dir, repo) = temp_repo(false);
+    write_file(dir.path(), "a.txt", "a\n");
+    let index = repo.index().unwrap();
+    index.add_bypath("a.txt");
+    let tree = index.write_tree().unwrap();
+    let sig = sample_sig();
+    let base_id = repo.commit(Some("HEAD"), &sig, &sig, None, "base", tree, []);
+    let base = repo.lookup_commit(&base_id).unwrap();
+    let next_id = repo.commit(None, &sig, &sig, None, "next", tree, [base]);
+
+    let heads = [repo.annotated_commit_lookup(&base_id).unwrap()];
+    let (analysis, _) = repo.merge_analysis(heads).unwrap();
+    assert!(analysis.up_to_date);
+
+    let heads = [repo.annotated_commit_lookup(&next_id).unwrap()];
+    let (analysis, _) = repo.merge_analysis(heads).unwrap();
+    assert!(analysis.fastforward);
+    assert!(!analysis.up_to_date);
+
+    assert_eq!(repo.state(), git2::GIT_REPOSITORY_STATE_NONE);
+    repo.merge(heads, &MergeOptions::new(), &CheckoutOptions::new()).unwrap();
+    assert_eq!(repo.state(), git2::GIT_REPOSITORY_STATE_MERGE);
+    repo.state_cleanup();
+    assert_eq!(repo.state(), git2::GIT_REPOSITORY_STATE_NONE);
+}
+
+#[test]
+fn stash() {
+    let (dir, repo) = temp_repo(false);
+    checkout_blob(&repo, "a.txt", "a\n");
+    let sig = sample_sig();
+
+    write_file(dir.path(), "a.txt", "changed\n");
+    let stash_id = repo.stash_save(&sig, Some("wip"), []).unwrap();
+    assert_eq!(read_file(dir.path(), "a.txt"), ~"a\n");
+    let stashes: ~[StashEntry] = repo.stashes().unwrap().collect();
+    assert_eq!(stashes.len(), 1);
+    assert_eq!(stashes[0].index, 0);
+    assert_eq!(stashes[0].id, stash_id);
+    assert!(stashes[0].message.contains("wip"));
+
+    repo.stash_pop(0, &StashApplyOptions::new()).unwrap();
+    assert_eq!(read_file(dir.path(), "a.txt"), ~"changed\n");
+    assert_eq!(repo.stashes().unwrap().count(), 0);
+    assert!(repo.stash_drop(0).is_err());
+}
+
+#[test]
+fn stash_reinstate_index() {
+    let (dir, repo) = temp_repo(false);
+    let base = checkout_blob(&repo, "a.txt", "a\n");
+    let staged = repo.blob_create_frombuffer("staged\n".as_bytes()).unwrap();
+    let sig = sample_sig();
+
+    write_file(dir.path(), "a.txt", "staged\n");
+    let index = repo.index().unwrap();
+    index.add_bypath("a.txt");
+    index.write();
+    write_file(dir.path(), "a.txt", "unstaged\n");
+    repo.stash_save(&sig, None, []).unwrap();
+    assert_eq!(read_file(dir.path(), "a.txt"), ~"a\n");
+    assert_eq!(index.write_tree().unwrap().id(), base.tree().id());
+
+    // without REINSTATE_INDEX, the staged change comes back unstaged
+    repo.stash_apply(0, &StashApplyOptions::new()).unwrap();
+    assert_eq!(read_file(dir.path(), "a.txt"), ~"unstaged\n");
+    assert_eq!(index.write_tree().unwrap().id(), base.tree().id());
+
+    force_checkout(&repo, base.tree());
+    let mut apply_opts = StashApplyOptions::new();
+    apply_opts.flags = ~[git2::stash::GIT_STASH_APPLY_REINSTATE_INDEX];
+    repo.stash_pop(0, &apply_opts).unwrap();
+    assert_eq!(read_file(dir.path(), "a.txt"), ~"unstaged\n");
+    let tree = index.write_tree().unwrap();
+    assert_eq!(tree.entry_byname("a.txt").unwrap().id(), staged.id());
+}
+
+#[test]
+fn config() {
+    let (dir, repo) = temp_repo(false);
+    let config = repo.config().unwrap().open_level(GIT_CONFIG_LEVEL_LOCAL).unwrap();
+
+    assert_eq!(config.get_str("ourteam.owner"), None);
+    config.set_str("ourteam.owner", "infra").unwrap();
+    config.set_bool("ourteam.enabled", true).unwrap();
+    config.set_i64("ourteam.quota", 1 << 40).unwrap();
+    assert_eq!(config.get_str("ourteam.owner"), Some(~"infra"));
+    assert_eq!(config.get_bool("ourteam.enabled"), Some(true));
+    assert_eq!(config.get_i64("ourteam.quota"), Some(1 << 40));
+
+    config.set_multivar("ourteam.reviewer", "^$", "alice").unwrap();
+    config.set_multivar("ourteam.reviewer", "^$", "bob").unwrap();
+    assert_eq!(config.get_multivar("ourteam.reviewer", None).unwrap(), ~[~"alice", ~"bob"]);
+    assert_eq!(config.get_multivar("ourteam.reviewer", Some("^b")).unwrap(), ~[~"bob"]);
+
+    let names: ~[~str] = config.entries(Some("^ourteam\\.")).unwrap().map(|e| e.name).collect();
+    assert_eq!(names.len(), 5);
+
+    // the local level is the repository's own config file
+    assert!(read_file(dir.path(), ".git/config").contains("owner = infra"));
+    let all_levels = repo.config().unwrap();
+    for entry in all_levels.entries(Some("^ourteam\\.owner$")).unwrap() {
+        match entry.level {
+            GIT_CONFIG_LEVEL_LOCAL => (),
+            _ => fail!(~"ourteam.owner read from another level"),
+        }
+    }
+
+    config.delete_entry("ourteam.owner").unwrap();
+    assert_eq!(config.get_str("ourteam.owner"), None);
+}
+
+#[test]
+fn config_ondisk() {
+    let dir = TempDir::new("git2_test").unwrap();
+    let path = dir.path().join(".gitmodules");
+    let config = Config::open_ondisk(path.as_str().unwrap()).unwrap();
+    config.set_str("submodule.lib.path", "lib").unwrap();
+    config.set_str("submodule.lib.url", "../lib.git").unwrap();
+
+    let snapshot = config.snapshot().unwrap();
+    config.set_str("submodule.lib.url", "../other.git").unwrap();
+    assert_eq!(snapshot.get_str("submodule.lib.url"), Some(~"../lib.git"));
+
+    let reopened = Config::open_ondisk(path.as_str().unwrap()).unwrap();
+    assert_eq!(reopened.get_str("submodule.lib.url"), Some(~"../other.git"));
+
+    assert_eq!(Config::parse_bool("yes"), Some(true));
+    assert_eq!(Config::parse_bool("off"), Some(false));
+    assert_eq!(Config::parse_bool("maybe"), None);
+    assert_eq!(Config::parse_i32("2k"), Some(2048));
+    assert_eq!(Config::parse_i64("3g"), Some(3 * 1024 * 1024 * 1024));
+    assert_eq!(Config::parse_i32("12x"), None);
+    let home = std::os::homedir().unwrap();
+    assert_eq!(Config::parse_path("~/foo"), Some(home.join("foo").as_str().unwrap().to_owned()));
+}
+
+#[test]
+fn signature_now() {
+    let before = extra::time::get_time().sec;
+    let sig = git2::Signature::now("김현강", "kimhyunkang@gmail.com").unwrap();
+    assert_eq!(sig.name, ~"김현강");
+    assert!(sig.when.time >= before);
+
+    assert!(git2::Signature::now("Evil <evil@example.com>", "a@example.com").is_err());
+    assert!(git2::Signature::now("name", "a@example.com>").is_err());
+    assert!(git2::Signature::now("multi\nline", "a@example.com").is_err());
+
+    let (_dir, repo) = temp_repo(false);
+    let config = repo.config().unwrap().open_level(GIT_CONFIG_LEVEL_LOCAL).unwrap();
+    config.set_str("user.name", "git2-rs").unwrap();
+    config.set_str("user.email", "git2-rs@example.com").unwrap();
+    let sig = repo.signature().unwrap();
+    assert_eq!(sig.name, ~"git2-rs");
+    assert_eq!(sig.email, ~"git2-rs@example.com");
+}
+
+#[test]
+fn time_formats() {
+    let time = git2::Time { time: 1371000000, offset: 9 * 60 };
+    assert_eq!(time.to_raw(), ~"1371000000 +0900");
+    assert_eq!(time.to_rfc2822(), ~"Wed, 12 Jun 2013 10:20:00 +0900");
+    assert_eq!(time.to_iso8601(), ~"2013-06-12 10:20:00 +0900");
+    assert_eq!(time.to_iso8601_strict(), ~"2013-06-12T10:20:00+09:00");
+
+    let west = git2::Time { time: 1371000000, offset: -(5 * 60 + 30) };
+    assert_eq!(west.to_iso8601_strict(), ~"2013-06-11T19:50:00-05:30");
+    assert_eq!(git2::Time::from_iso8601("2013-06-11T19:50:00-05:30"), Some(west));
+
+    assert_eq!(git2::Time::from_raw("1371000000 +0900"), Some(time));
+    assert_eq!(git2::Time::from_rfc2822("Wed, 12 Jun 2013 10:20:00 +0900"), Some(time));
+    assert_eq!(git2::Time::from_iso8601("2013-06-12 10:20:00 +0900"), Some(time));
+    assert_eq!(git2::Time::from_iso8601("2013-06-12"), None);
+    assert_eq!(git2::Time::from_iso8601("2013-02-31 10:20:00 +0900"), None);
+    assert_eq!(git2::Time::from_iso8601("2013-02-29 10:20:00 +0900"), None);
+    assert!(git2::Time::from_iso8601("2012-02-29 10:20:00 +0900").is_some());
+    assert_eq!(git2::Time::from_iso8601("1900-02-29 10:20:00 +0900"), None);
+    assert!(git2::Time::from_iso8601("2000-02-29 10:20:00 +0900").is_some());
+    assert_eq!(git2::Time::from_iso8601("2013-06-12 -1:20:00 +0900"), None);
+    assert_eq!(git2::Time::from_rfc2822("Wed, 12 Jun 2013 10:-20:00 +0900"), None);
+
+    let day = 24 * 60 * 60;
+    assert_eq!(time.to_relative(1371000000 + 30), ~"30 seconds ago");
+    assert_eq!(time.to_relative(1371000000 + 3 * day), ~"3 days ago");
+    assert_eq!(time.to_relative(1371000000 + 400 * day), ~"1 year, 1 month ago");
+    assert_eq!(time.to_relative(1371000000 - 1), ~"in the future");
+    let parsed = git2::Time::from_relative("3 days ago", 1371000000 + 3 * day).unwrap();
+    assert_eq!(parsed.time, 1371000000);
+
+    let sig = git2::Signature::from_ident("김현강 <kimhyunkang@gmail.com> 1371000000 +0900");
+    let sig = sig.unwrap();
+    assert_eq!(sig.name, ~"김현강");
+    assert_eq!(sig.email, ~"kimhyunkang@gmail.com");
+    assert_eq!(sig.when, time);
+    assert_eq!(sig.to_ident(), ~"김현강 <kimhyunkang@gmail.com> 1371000000 +0900");
+    assert!(git2::Signature::from_ident("no email 1371000000 +0900").is_none());
+}
+
+#[test]
+fn remotes() {
+    let (_dir, repo) = temp_repo(false);
+    assert_eq!(repo.remotes().unwrap(), ~[]);
+
+    let origin = repo.remote_create("origin", "file:///srv/git/origin.git").unwrap();
+    assert_eq!(origin.name(), Some(~"origin"));
+    assert_eq!(origin.fetch_refspecs(), ~[~"+refs/heads/*:refs/remotes/origin/*"]);
+
+    repo.remote_create("mirror", "file:///srv/git/old-mirror.git").unwrap();
+    repo.remote_set_url("mirror", "file:///srv/git/mirror.git").unwrap();
+    repo.remote_set_pushurl("mirror", Some("file:///srv/git/mirror-push.git")).unwrap();
+    repo.remote_add_push("mirror", "+refs/heads/*:refs/heads/*").unwrap();
+    repo.remote_set_autotag("mirror", git2::remote::GIT_REMOTE_DOWNLOAD_TAGS_ALL).unwrap();
+
+    let mirror = repo.find_remote("mirror").unwrap();
+    assert_eq!(mirror.url(), ~"file:///srv/git/mirror.git");
+    assert_eq!(mirror.pushurl(), Some(~"file:///srv/git/mirror-push.git"));
+    assert_eq!(mirror.push_refspecs(), ~[~"+refs/heads/*:refs/heads/*"]);
+    assert_eq!(mirror.autotag(), git2::remote::GIT_REMOTE_DOWNLOAD_TAGS_ALL);
+
+    assert_eq!(repo.remote_rename("mirror", "backup").unwrap(), ~[]);
+    assert!(repo.find_remote("mirror").is_none());
+    repo.remote_delete("backup").unwrap();
+    assert_eq!(repo.remotes().unwrap(), ~[~"origin"]);
+
+    let mut anonymous = repo.remote_create_anonymous("file:///srv/git/other.git").unwrap();
+    assert_eq!(anonymous.name(), None);
+    assert!(anonymous.set_url("file:///srv/git/elsewhere.git").is_err());
+}
+
+#[test]
+fn remote_setters() {
+    let (_dir, repo) = temp_repo(false);
+    let mut origin = repo.remote_create("origin", "file:///srv/git/origin.git").unwrap();
+    origin.set_url("file:///srv/git/moved.git").unwrap();
+    origin.set_pushurl(Some("file:///srv/git/moved-push.git")).unwrap();
+    origin.add_fetch("+refs/tags/*:refs/tags/*").unwrap();
+    origin.add_push("refs/heads/master:refs/heads/master").unwrap();
+    origin.set_autotag(git2::remote::GIT_REMOTE_DOWNLOAD_TAGS_NONE).unwrap();
+    assert_eq!(origin.url(), ~"file:///srv/git/moved.git");
+    assert_eq!(origin.pushurl(), Some(~"file:///srv/git/moved-push.git"));
+    assert_eq!(origin.fetch_refspecs(), ~[~"+refs/heads/*:refs/remotes/origin/*",
+                                          ~"+refs/tags/*:refs/tags/*"]);
+    assert_eq!(origin.autotag(), git2::remote::GIT_REMOTE_DOWNLOAD_TAGS_NONE);
+
+    // refspecs are removed by exact match, not as patterns
+    assert!(origin.remove_fetch("+refs/tags/.*").is_err());
+    origin.remove_fetch("+refs/heads/*:refs/remotes/origin/*").unwrap();
+    origin.remove_push("refs/heads/master:refs/heads/master").unwrap();
+    assert_eq!(origin.fetch_refspecs(), ~[~"+refs/tags/*:refs/tags/*"]);
+    assert_eq!(origin.push_refspecs(), ~[]);
+    origin.set_pushurl(None).unwrap();
+    assert_eq!(origin.pushurl(), None);
+
+    let found = repo.find_remote("origin").unwrap();
+    assert_eq!(found.url(), ~"file:///srv/git/moved.git");
+    assert_eq!(found.fetch_refspecs(), ~[~"+refs/tags/*:refs/tags/*"]);
+}
+
+struct UpdatedRefs {
+    names: ~[~str],
+}
+
+impl RemoteCallbacks for UpdatedRefs {
+    fn update_tips(&mut self, refname: &str, _old: &git2::OID, _new: &git2::OID) -> bool {
+        self.names.push(refname.to_owned());
+        true
+    }
+}
+
+#[test]
+fn remote_fetch() {
+    let (src_dir, src) = temp_repo(true);
+    let master = commit_blob(&src, Some("refs/heads/master"), "a.txt", "a\n");
+    commit_blob(&src, Some("refs/heads/topic"), "b.txt", "b\n");
+
+    let (_dir, repo) = temp_repo(false);
+    let url = format!("file://{}", src_dir.path().as_str().unwrap());
+    let origin = repo.remote_create("origin", url).unwrap();
+
+    let mut updated = UpdatedRefs { names: ~[] };
+    origin.fetch([], &FetchOptions::new(), &mut updated, None).unwrap();
+    updated.names.sort();
+    assert_eq!(updated.names, ~[~"refs/remotes/origin/master", ~"refs/remotes/origin/topic"]);
+    assert_eq!(repo.lookup("refs/remotes/origin/master").unwrap().resolve(), master);
+    assert!(origin.stats().received_objects > 0);
+
+    git2::reference::branch_delete(src.lookup_branch("topic", false).unwrap());
+    let mut opts = FetchOptions::new();
+    opts.prune = GIT_FETCH_PRUNE;
+    origin.fetch([], &opts, &mut NoCallbacks, None).unwrap();
+    assert!(repo.lookup("refs/remotes/origin/topic").is_none());
+    assert!(repo.lookup("refs/remotes/origin/master").is_some());
+}
+
+struct PushStatus {
+    refs: ~[(~str, Option<~str>)],
+    // stage, current and total of each pack building report
+    packing: ~[(int, uint, uint)],
+}
+
+impl PushStatus {
+    fn new() -> PushStatus {
+        PushStatus { refs: ~[], packing: ~[] }
+    }
+}
+
+impl RemoteCallbacks for PushStatus {
+    fn push_update_reference(&mut self, refname: &str, status: Option<&str>) -> bool {
+        self.refs.push((refname.to_owned(), status.map(|s| s.to_owned())));
+        true
+    }
+
+    fn pack_progress(&mut self, stage: PackBuilderStage, current: uint, total: uint) -> bool {
+        self.packing.push((stage as int, current, total));
+        true
+    }
+}
+
+#[test]
+fn remote_push() {
+    let (dst_dir, dst) = temp_repo(true);
+
+    let (_dir, repo) = temp_repo(false);
+    let master = commit_blob(&repo, Some("refs/heads/master"), "a.txt", "a\n");
+    commit_blob(&repo, Some("refs/heads/topic"), "b.txt", "b\n");
+    let other = commit_blob(&repo, Some("refs/heads/other"), "c.txt", "c\n");
+    let url = format!("file://{}", dst_dir.path().as_str().unwrap());
+    let origin = repo.remote_create("origin", url).unwrap();
+
+    let mut status = PushStatus::new();
+    origin.push(["refs/heads/master", "refs/heads/topic"], &PushOptions::new(),
+                &mut status).unwrap();
+    status.refs.sort();
+    assert_eq!(status.refs, ~[(~"refs/heads/master", None), (~"refs/heads/topic", None)]);
+    // objects are reported as they are added, with no total yet
+    let adding = GIT_PACKBUILDER_ADDING_OBJECTS as int;
+    assert!(status.packing.iter().any(|&(stage, current, _)| stage == adding && current > 0));
+    assert!(status.packing.iter().all(|&(stage, current, total)| {
+        stage == adding || current <= total
+    }));
+    assert_eq!(dst.lookup("refs/heads/master").unwrap().resolve(), master);
+
+    // other does not descend from master
+    assert!(origin.push(["refs/heads/other:refs/heads/master"], &PushOptions::new(),
+                        &mut NoCallbacks).is_err());
+    assert_eq!(dst.lookup("refs/heads/master").unwrap().resolve(), master);
+    origin.push(["+refs/heads/other:refs/heads/master"], &PushOptions::new(),
+                &mut NoCallbacks).unwrap();
+    assert_eq!(dst.lookup("refs/heads/master").unwrap().resolve(), other);
+
+    origin.push([":refs/heads/topic"], &PushOptions::new(), &mut NoCallbacks).unwrap();
+    assert!(dst.lookup("refs/heads/topic").is_none());
+}
+
+#[test]
+fn remote_list() {
+    let (src_dir, src) = temp_repo(true);
+    let master = commit_blob(&src, Some("refs/heads/master"), "a.txt", "a\n");
+    let topic = commit_blob(&src, Some("refs/heads/topic"), "b.txt", "b\n");
+
+    let (_dir, repo) = temp_repo(false);
+    let url = format!("file://{}", src_dir.path().as_str().unwrap());
+    let origin = repo.remote_create("origin", url).unwrap();
+    assert!(origin.list().is_err());
+
+    origin.connect(GIT_DIRECTION_FETCH, &mut NoCallbacks).unwrap();
+    assert!(origin.connected());
+    assert_eq!(origin.default_branch(), Some(~"refs/heads/master"));
+    let heads = origin.list().unwrap();
+    let names: ~[~str] = heads.iter().map(|h| h.name.clone()).collect();
+    assert_eq!(names, ~[~"HEAD", ~"refs/heads/master", ~"refs/heads/topic"]);
+    assert_eq!(heads[0].symref_target, Some(~"refs/heads/master"));
+    assert_eq!(heads[0].oid, master);
+    assert_eq!(heads[1].symref_target, None);
+    assert_eq!(heads[2].oid, topic);
+    assert!(heads.iter().all(|h| h.local_oid.is_none()));
+    origin.disconnect();
+    assert!(!origin.connected());
+
+    origin.fetch([], &FetchOptions::new(), &mut NoCallbacks, None).unwrap();
+    origin.connect(GIT_DIRECTION_FETCH, &mut NoCallbacks).unwrap();
+    let heads = origin.list().unwrap();
+    assert_eq!(heads[0].local_oid, None);
+    assert_eq!(heads[1].local_oid, Some(master));
+    assert_eq!(heads[2].local_oid, Some(topic));
+}
+
+#[test]
+fn credential_helper() {
+    let dir = TempDir::new("git2_test").unwrap();
+    let cfg = git2::Config::open_ondisk(dir.path().join("config").as_str().unwrap()).unwrap();
+    let url = "https://alice@example.com/repo.git";
+    assert!(Cred::credential_helper(&cfg, url, None).is_none());
+
+    // the helper sees what git would send it, and only answers for example.com
+    cfg.set_str("credential.helper", "!f() { grep -q host=example.com && \
+                 echo password=s3cret; }; f").unwrap();
+    match Cred::credential_helper(&cfg, url, Some("alice")) {
+        Some(CredUserPassPlaintext(user, password)) => {
+            assert_eq!(user, ~"alice");
+            assert_eq!(password, ~"s3cret");
+        },
+        _ => fail!("expected a username and password"),
+    }
+    assert!(Cred::credential_helper(&cfg, "https://example.org/repo.git",
+                                    Some("alice")).is_none());
+    // a password without any username is not enough
+    assert!(Cred::credential_helper(&cfg, url, None).is_none());
+
+    // an empty value resets the helpers configured before it
+    cfg.set_multivar("credential.helper", "^$", "").unwrap();
+    assert_eq!(cfg.get_multivar("credential.helper", None).unwrap().len(), 2);
+    assert!(Cred::credential_helper(&cfg, url, Some("alice")).is_none());
+    cfg.set_multivar("credential.helper", "^!echo", "!echo password=later").unwrap();
+    assert_eq!(cfg.get_multivar("credential.helper", None).unwrap().len(), 3);
+    match Cred::credential_helper(&cfg, url, Some("alice")) {
+        Some(CredUserPassPlaintext(_, password)) => assert_eq!(password, ~"later"),
+        _ => fail!("expected the helper after the reset to answer"),
+    }
+}
+
+// records what a transport asks of the callbacks
+struct AskingCallbacks {
+    credentials: ~[(~str, Option<~str>, ~[CredType])],
+    certificates: ~[(bool, ~str, bool)],
+    trust: bool,
+}
+
+impl AskingCallbacks {
+    fn new(trust: bool) -> AskingCallbacks {
+        AskingCallbacks { credentials: ~[], certificates: ~[], trust: trust }
+    }
+}
+
+impl RemoteCallbacks for AskingCallbacks {
+    fn credentials(&mut self, url: &str, username: Option<&str>,
+                   allowed: &[CredType]) -> Option<Cred> {
+        self.credentials.push((url.to_owned(), username.map(|u| u.to_owned()), allowed.to_owned()));
+        if self.credentials.len() == 1 {
+            Some( CredUserPassPlaintext(~"alice", ~"s3cret") )
+        } else {
+            None
+        }
+    }
+
+    fn certificate_check(&mut self, cert: &Cert, valid: bool, host: &str) -> Option<bool> {
+        let x509 = match *cert {
+            CertX509(ref der) => !der.is_empty(),
+            _ => false,
+        };
+        self.certificates.push((valid, host.to_owned(), x509));
+        Some(self.trust)
+    }
+}
+
+// a free port on the loopback interface
+fn free_port() -> u16 {
+    let addr = SocketAddr { ip: Ipv4Addr(127, 0, 0, 1), port: 0 };
+    TcpListener::bind(addr).unwrap().socket_name().unwrap().port
+}
+
+// a stand-in HTTP server on 127.0.0.1 answering 401 until a request carries
+// credentials, then 404; the Authorization header of each request is sent back
+fn http_auth_standin() -> (u16, Port<Option<~str>>) {
+    let addr = SocketAddr { ip: Ipv4Addr(127, 0, 0, 1), port: 0 };
+    let mut listener = TcpListener::bind(addr).unwrap();
+    let port = listener.socket_name().unwrap().port;
+    let (headers, chan) = Chan::new();
+    spawn(proc() {
+        let mut acceptor = listener.listen().unwrap();
+        loop {
+            let mut stream = acceptor.accept().unwrap();
+            let mut request = ~"";
+            while !request.ends_with("\r\n\r\n") {
+                match stream.read_byte() {
+                    Some(b) => request.push_char(b as char),
+                    None => break,
+                }
+            }
+            let auth = request.lines().find(|l| l.starts_with("Authorization: "))
+                .map(|l| l.slice_from("Authorization: ".len()).trim_right().to_owned());
+            let answered = auth.is_some();
+            let status = if answered {
+                "404 Not Found"
+            } else {
+                "401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"git\""
+            };
+            stream.write(format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                                 status).as_bytes());
+            chan.send(auth);
+            if answered {
+                break;
+            }
+        }
+    });
+    (port, headers)
+}
+
+#[test]
+fn remote_credentials() {
+    let (port, auths) = http_auth_standin();
+    let (_dir, repo) = temp_repo(true);
+    let url = format!("http://127.0.0.1:{}/repo.git", port);
+    let origin = repo.remote_create("origin", url).unwrap();
+
+    let mut callbacks = AskingCallbacks::new(true);
+    assert!(origin.fetch([], &FetchOptions::new(), &mut callbacks, None).is_err());
+    assert_eq!(callbacks.credentials.len(), 1);
+    let (ref asked_url, ref username, ref allowed) = callbacks.credentials[0];
+    assert!(asked_url.starts_with(format!("http://127.0.0.1:{}/", port)));
+    assert_eq!(*username, None);
+    assert!(allowed.contains(&GIT_CREDTYPE_USERPASS_PLAINTEXT));
+    // plain HTTP has no certificate to check
+    assert!(callbacks.certificates.is_empty());
+
+    assert_eq!(auths.recv(), None);
+    // "alice:s3cret" in base64
+    assert_eq!(auths.recv(), Some(~"Basic YWxpY2U6czNjcmV0"));
+}
+
+// a stand-in HTTPS server with a self-signed certificate, killed when dropped
+struct TlsStandin {
+    process: run::Process,
+    port: u16,
+}
+
+impl TlsStandin {
+    // None if openssl is not installed
+    fn start(dir: &Path) -> Option<TlsStandin> {
+        let key = dir.join("key.pem").as_str().unwrap().to_owned();
+        let cert = dir.join("cert.pem").as_str().unwrap().to_owned();
+        let args = [~"req", ~"-x509", ~"-newkey", ~"rsa:2048", ~"-nodes", ~"-days", ~"1",
+                    ~"-subj", ~"/CN=localhost", ~"-keyout", key.clone(), ~"-out", cert.clone()];
+        let output = match run::process_output("openssl", args) {
+            Some(output) => output,
+            None => return None,
+        };
+        assert!(output.status.success(), str::from_utf8(output.error).to_owned());
+
+        // another process may take the free port before s_server binds it,
+        // in which case s_server exits and we try the next one
+        let mut attempts = 0;
+        loop {
+            let port = free_port();
+            let mut process = run::Process::new("openssl",
+                                                [~"s_server", ~"-www", ~"-accept", port.to_str(),
+                                                 ~"-key", key.clone(), ~"-cert", cert.clone()],
+                                                run::ProcessOptions::new())
+                .expect("could not run openssl s_server");
+            // wait until the server listens
+            let mut banner = ~"";
+            while !banner.contains("ACCEPT") {
+                match process.output().read_byte() {
+                    Some(b) => banner.push_char(b as char),
+                    None => break,
+                }
+            }
+            if banner.contains("ACCEPT") {
+                return Some( TlsStandin { process: process, port: port } );
+            }
+            process.finish();
+            attempts += 1;
+            if attempts == 5 {
+                fail!("openssl s_server exited: {}", banner);
+            }
+        }
+    }
+}
+
+impl Drop for TlsStandin {
+    fn drop(&mut self) {
+        self.process.force_destroy();
+    }
+}
+
+#[test]
+fn remote_certificate_check() {
+    let dir = TempDir::new("git2_test").unwrap();
+    let server = match TlsStandin::start(dir.path()) {
+        Some(server) => server,
+        // nothing to check against without openssl
+        None => return,
+    };
+    let path = dir.path().join("repo");
+    let repo = git2::Repository::init(path.as_str().unwrap(), true).unwrap();
+    let url = format!("https://127.0.0.1:{}/repo.git", server.port);
+    let origin = repo.remote_create("origin", url).unwrap();
+
+    // the self-signed certificate cannot be verified, and the callback refuses it
+    let mut callbacks = AskingCallbacks::new(false);
+    assert!(origin.connect(GIT_DIRECTION_FETCH, &mut callbacks).is_err());
+    assert_eq!(callbacks.certificates, ~[(false, ~"127.0.0.1", true)]);
+    assert!(callbacks.credentials.is_empty());
+
+    // trusting it gets past the handshake; the server does not speak git though
+    let mut callbacks = AskingCallbacks::new(true);
+    assert!(origin.fetch([], &FetchOptions::new(), &mut callbacks, None).is_err());
+    assert_eq!(callbacks.certificates, ~[(false, ~"127.0.0.1", true)]);
+}
+
+#[test]
+fn clone_with_callbacks() {
+    let (src_dir, src) = temp_repo(true);
+    let master = commit_blob(&src, Some("refs/heads/master"), "a.txt", "a\n");
+
+    let dir = TempDir::new("git2_test").unwrap();
+    let url = format!("file://{}", src_dir.path().as_str().unwrap());
+    let path = dir.path().join("clone");
+    let repo = git2::Repository::clone_with_callbacks(url, path.as_str().unwrap(),
+                                                      &mut NoCallbacks).unwrap();
+    assert_eq!(repo.head().unwrap().resolve(), master);
+    assert!(path.join("a.txt").exists());
+}
+
+// serves a single branch at fake://store, holding the commit of fake.txt
+struct FakeTransport {
+    connected: bool,
+    wanted: uint,
+}
+
+// builds a FakeTransport for each remote, reporting the remote's url,
+// and reports being dropped
+struct FakeStore {
+    urls: Chan<~str>,
+}
+
+impl TransportFactory for FakeStore {
+    fn new_transport(&mut self, remote: &git2::remote::Remote)
+        -> Result<~Transport, (~str, git2::GitError)> {
+        self.urls.send(remote.url());
+        Ok( ~FakeTransport { connected: false, wanted: 0 } as ~Transport )
+    }
+}
+
+impl Drop for FakeStore {
+    fn drop(&mut self) {
+        self.urls.send(~"dropped");
+    }
+}
+
+impl Transport for FakeTransport {
+    fn connect(&mut self, url: &str, _direction: Direction) -> Result<(), (~str, git2::GitError)> {
+        if url != "fake://store" {
+            return Err( (format!("no store at {}", url), git2::GITERR_NET) );
+        }
+        self.connected = true;
+        Ok(())
+    }
+
+    fn ls(&mut self) -> Result<~[RemoteHead], (~str, git2::GitError)> {
+        let (_dir, scratch) = temp_repo(true);
+        Ok( ~[RemoteHead {
+            name: ~"refs/heads/master",
+            oid: commit_blob(&scratch, None, "fake.txt", "fake\n"),
+            symref_target: None,
+            local_oid: None,
+        }] )
+    }
+
+    fn negotiate_fetch(&mut self, _repo: &git2::Repository, wants: &[RemoteHead])
+        -> Result<(), (~str, git2::GitError)> {
+        self.wanted = wants.len();
+        Ok(())
+    }
+
+    fn download_pack(&mut self, repo: &git2::Repository, stats: &mut TransferProgress)
+        -> Result<(), (~str, git2::GitError)> {
+        if self.wanted > 0 {
+            commit_blob(repo, None, "fake.txt", "fake\n");
+            stats.total_objects = 3;
+            stats.received_objects = 3;
+            stats.indexed_objects = 3;
+        }
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    fn close(&mut self) -> Result<(), (~str, git2::GitError)> {
+        self.connected = false;
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_transport() {
+    let (urls, chan) = Chan::new();
+    transport_register("fake", ~FakeStore { urls: chan } as ~TransportFactory).unwrap();
+    let (rejected, chan) = Chan::new();
+    assert!(transport_register("fake", ~FakeStore { urls: chan } as ~TransportFactory).is_err());
+    assert_eq!(rejected.recv(), ~"dropped");
+
+    let (_dir, repo) = temp_repo(false);
+    let fake = repo.remote_create("fake", "fake://store").unwrap();
+    fake.connect(GIT_DIRECTION_FETCH, &mut NoCallbacks).unwrap();
+    assert_eq!(urls.recv(), ~"fake://store");
+    let heads = fake.list().unwrap();
+    assert_eq!(heads.len(), 1);
+    assert_eq!(heads[0].name, ~"refs/heads/master");
+    fake.disconnect();
+
+    fake.fetch([], &FetchOptions::new(), &mut NoCallbacks, None).unwrap();
+    let oid = repo.lookup("refs/remotes/fake/master").unwrap().resolve();
+    assert_eq!(oid, heads[0].oid);
+    assert!(repo.lookup_commit(&oid).is_some());
+    assert_eq!(fake.stats().received_objects, 3);
+    let refspec = "refs/remotes/fake/master:refs/heads/other";
+    match fake.push([refspec], &PushOptions::new(), &mut NoCallbacks) {
+        Err((msg, kind)) => {
+            assert!(msg.contains("not supported"));
+            assert_eq!(kind as int, git2::GITERR_NET as int);
+        },
+        Ok(()) => fail!("pushing through a Transport must fail"),
+    }
+
+    let missing = repo.remote_create("missing", "fake://elsewhere").unwrap();
+    assert!(missing.fetch([], &FetchOptions::new(), &mut NoCallbacks, None).is_err());
+    assert!(missing.push(["refs/heads/master"], &PushOptions::new(), &mut NoCallbacks).is_err());
+
+    transport_unregister("fake").unwrap();
+    let mut reports = ~[];
+    loop {
+        match urls.try_recv() {
+            Some(report) => reports.push(report),
+            None => break,
+        }
+    }
+    assert!(reports.contains(&~"fake://elsewhere"));
+    assert_eq!(reports.last(), &~"dropped");
+    assert!(fake.fetch([], &FetchOptions::new(), &mut NoCallbacks, None).is_err());
+}
+
+// answers the requests of a stateless smart subtransport by running the
+// git server programs on the repository at the path of a loop:// URL,
+// and framing their answers the way an HTTP server does
+struct LoopbackStream {
+    args: ~[~str],
+    // the service announced before a reference advertisement
+    service: Option<~str>,
+    request: ~[u8],
+    response: Option<~[u8]>,
+    pos: uint,
+}
+
+impl LoopbackStream {
+    fn respond(&self) -> Result<~[u8], (~str, git2::GitError)> {
+        let mut process = match run::Process::new("git", self.args, run::ProcessOptions::new()) {
+            Some(process) => process,
+            None => return Err( (~"could not run git", git2::GITERR_OS) ),
+        };
+        process.input().write(self.request);
+        process.close_input();
+        let output = process.finish_with_output();
+        if !output.status.success() {
+            return Err( (format!("git {} failed: {}", self.args[0], str::from_utf8(output.error)),
+                         git2::GITERR_NET) );
+        }
+        let mut response = ~[];
+        match self.service {
+            // a pkt-line naming the service, then a flush
+            Some(ref service) => {
+                let line = "# service=" + service.as_slice() + "\n";
+                response.push_all(format!("{:04x}{}0000", line.len() + 4, line).as_bytes());
+            },
+            None => (),
+        }
+        response.push_all(output.output);
+        Ok(response)
+    }
+}
+
+impl SmartSubtransportStream for LoopbackStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<uint, (~str, git2::GitError)> {
+        if self.response.is_none() {
+            match self.respond() {
+                Ok(response) => self.response = Some(response),
+                Err(e) => return Err(e),
+            }
+        }
+        let response = self.response.get_ref();
+        let n = std::cmp::min(buf.len(), response.len() - self.pos);
+        for i in range(0, n) {
+            buf[i] = response[self.pos + i];
+        }
+        self.pos += n;
+        Ok(n)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), (~str, git2::GitError)> {
+        self.request.push_all(data);
+        Ok(())
+    }
+}
+
+struct Loopback;
+
+impl SmartSubtransportFactory for Loopback {
+    fn new_subtransport(&mut self, remote: &git2::remote::Remote)
+        -> Result<~SmartSubtransport, (~str, git2::GitError)> {
+        if !remote.url().starts_with("loop://") {
+            return Err( (format!("not a loop:// url: {}", remote.url()), git2::GITERR_NET) );
+        }
+        Ok( ~Loopback as ~SmartSubtransport )
+    }
+}
+
+impl SmartSubtransport for Loopback {
+    fn action(&mut self, url: &str, service: SmartService)
+        -> Result<~SmartSubtransportStream, (~str, git2::GitError)> {
+        let path = url.slice_from("loop://".len()).to_owned();
+        let (program, advertise) = match service {
+            GIT_SERVICE_UPLOADPACK_LS => ("upload-pack", true),
+            GIT_SERVICE_UPLOADPACK => ("upload-pack", false),
+            GIT_SERVICE_RECEIVEPACK_LS => ("receive-pack", true),
+            _ => ("receive-pack", false),
+        };
+        let mut args = ~[program.to_owned(), ~"--stateless-rpc"];
+        if advertise {
+            args.push(~"--advertise-refs");
+        }
+        args.push(path);
+        let service = if advertise { Some( "git-" + program ) } else { None };
+        Ok( ~LoopbackStream { args: args, service: service, request: ~[], response: None, pos: 0 }
+            as ~SmartSubtransportStream )
+    }
+}
+
+#[test]
+fn smart_subtransport() {
+    smart_subtransport_register("loop", true, ~Loopback as ~SmartSubtransportFactory).unwrap();
+
+    let (src_dir, src) = temp_repo(true);
+    let master = commit_blob(&src, Some("refs/heads/master"), "a.txt", "a\n");
+
+    let (dir, repo) = temp_repo(false);
+    let url = format!("loop://{}", src_dir.path().as_str().unwrap());
+    let origin = repo.remote_create("origin", url).unwrap();
+    origin.fetch([], &FetchOptions::new(), &mut NoCallbacks, None).unwrap();
+    assert_eq!(repo.lookup("refs/remotes/origin/master").unwrap().resolve(), master);
+    assert!(repo.lookup_commit(&master).is_some());
+
+    let topic = commit_blob(&repo, Some("refs/heads/topic"), "b.txt", "b\n");
+    origin.push(["refs/heads/topic"], &PushOptions::new(), &mut NoCallbacks).unwrap();
+    assert_eq!(src.lookup("refs/heads/topic").unwrap().resolve(), topic);
+
+    // the server's rejection reaches the callbacks
+    src.config().unwrap().set_bool("receive.denyDeletes", true).unwrap();
+    let mut status = PushStatus::new();
+    origin.push([":refs/heads/topic"], &PushOptions::new(), &mut status).unwrap();
+    assert_eq!(status.refs, ~[(~"refs/heads/topic", Some(~"deletion prohibited"))]);
+    assert_eq!(src.lookup("refs/heads/topic").unwrap().resolve(), topic);
+
+    // a failing server program fails the fetch
+    let missing_url = format!("loop://{}", dir.path().join("missing").as_str().unwrap());
+    let missing = repo.remote_create("missing", missing_url).unwrap();
+    assert!(missing.fetch([], &FetchOptions::new(), &mut NoCallbacks, None).is_err());
+
+    transport_unregister("loop").unwrap();
+}
+
+#[test]
+fn tree_walk() {
+    let (_dir, repo) = temp_repo(true);
+    let tree = nested_tree(&repo);
+
+    let mut visited = ~[];
+    tree.walk(GIT_TREEWALK_PRE, |root, entry| {
+        visited.push(format!("{}{}", root, entry.name()));
+        git2::WalkPass
+    }).unwrap();
+    assert_eq!(visited, ~[~"a.txt", ~"dir", ~"dir/b.txt", ~"dir/sub", ~"dir/sub/c.txt"]);
+
+    let mut visited = ~[];
+    tree.walk(GIT_TREEWALK_POST, |root, entry| {
+        visited.push(format!("{}{}", root, entry.name()));
+        git2::WalkPass
+    }).unwrap();
+    assert_eq!(visited, ~[~"a.txt", ~"dir/b.txt", ~"dir/sub/c.txt", ~"dir/sub", ~"dir"]);
+
+    let mut visited = ~[];
+    tree.walk(GIT_TREEWALK_PRE, |root, entry| {
+        let path = format!("{}{}", root, entry.name());
+        let walk = if path == ~"dir/sub" { git2::WalkSkip } else { git2::WalkPass };
+        visited.push(path);
+        walk
+    }).unwrap();
+    assert_eq!(visited, ~[~"a.txt", ~"dir", ~"dir/b.txt", ~"dir/sub"]);
+
+    let mut visited = ~[];
+    tree.walk(GIT_TREEWALK_PRE, |root, entry| {
+        visited.push(format!("{}{}", root, entry.name()));
+        git2::WalkStop
+    }).unwrap();
+    assert_eq!(visited, ~[~"a.txt"]);
+
+    let paths = tree_paths(tree);
+    assert_eq!(paths, ~[~"a.txt", ~"dir/b.txt", ~"dir/sub/c.txt"]);
+    assert_eq!(tree.paths().take(1).map(|p| p.unwrap()).collect::<~[~str]>(), ~[~"a.txt"]);
+}
+
+#[test]
+fn tree_paths_missing_subtree() {
+    let dir = TempDir::new("git2_test").unwrap();
+    let root_id = {
+        let repo = git2::Repository::init(dir.path().as_str().unwrap(), true).unwrap();
+        let tree = nested_tree(&repo);
+        let sub = tree.entry_bypath("dir/sub").unwrap().id().to_str();
+        fs::unlink(&dir.path().join_many(["objects", sub.slice_to(2), sub.slice_from(2)]));
+        *tree.id()
+    };
+
+    // a fresh handle, so that the subtree is not served from the object cache
+    let repo = git2::Repository::open(dir.path().as_str().unwrap()).unwrap();
+    let tree = repo.lookup_tree(&root_id).unwrap();
+    let mut paths = tree.paths();
+    assert_eq!(paths.next().unwrap().unwrap(), ~"a.txt");
+    assert_eq!(paths.next().unwrap().unwrap(), ~"dir/b.txt");
+    assert!(paths.next().unwrap().is_err());
+    assert!(paths.next().is_none());
+}
+
+#[test]
+fn tree_entries() {
+    let (_dir, repo) = temp_repo(true);
+    let tree = nested_tree(&repo);
+
+    assert_eq!(tree.len(), 2);
+    assert_eq!(tree[0].name(), ~"a.txt");
+    assert_eq!(tree[1].name(), ~"dir");
+    assert!(tree.entry_byindex(2).is_none());
+    let names: ~[~str] = tree.iter().map(|e| e.name()).collect();
+    assert_eq!(names, ~[~"a.txt", ~"dir"]);
+
+    let blob = tree[0].to_blob(&repo).unwrap().unwrap();
+    blob.rawcontent_as_slice(|content| assert_eq!(content, "x\n".as_bytes()));
+    assert!(tree[0].to_tree(&repo).unwrap().is_none());
+
+    let subtree = tree[1].to_tree(&repo).unwrap().unwrap();
+    assert_eq!(subtree.len(), 2);
+    assert_eq!(subtree.id(), tree[1].id());
+    match tree[1].to_object(&repo) {
+        Ok(git2::ObjTree(t)) => assert_eq!(t.len(), 2),
+        _ => fail!("expected a tree"),
+    }
+
+    // the commit of a submodule is not in the repository
+    let builder = git2::tree::TreeBuilder::new(&repo).unwrap();
+    let missing = from_str("21002f5d3f411fe990e13604273a51cd598a4a51").unwrap();
+    builder.insert("sub", &missing, git2::GIT_FILEMODE_COMMIT).unwrap();
+    let tree = repo.lookup_tree(&builder.write().unwrap()).unwrap();
+    assert!(tree[0].to_object(&repo).is_err());
+    assert!(tree[0].to_tree(&repo).is_err());
+}
+
+#[test]
+fn treebuilder() {
+    let (_dir, repo) = temp_repo(true);
+    let tree = nested_tree(&repo);
+    let builder = git2::tree::TreeBuilder::from_tree(tree).unwrap();
+    let blob = tree.entry_byname("a.txt").unwrap();
+
+    assert!(builder.get("a.txt").is_some());
+    assert!(builder.get("missing.txt").is_none());
+    for name in ["", ".", "..", "x/y.txt"].iter() {
+        match builder.insert(*name, blob.id(), git2::GIT_FILEMODE_BLOB) {
+            Err((_, klass)) => assert_eq!(klass, git2::GITERR_TREE),
+            Ok(_) => fail!("inserted an entry named '{}'", *name),
+        }
+    }
+    assert!(builder.insert("new.txt", blob.id(), git2::GIT_FILEMODE_NEW).is_err());
+
+    builder.insert("b.txt", blob.id(), git2::GIT_FILEMODE_BLOB_EXECUTABLE).unwrap();
+    let names: ~[~str] = builder.iter().map(|e| e.name()).collect();
+    assert_eq!(names, ~[~"a.txt", ~"b.txt", ~"dir"]);
+
+    builder.filter(|entry| entry.name() != ~"a.txt");
+    assert_eq!(builder.entrycount(), 2);
+    let filtered = repo.lookup_tree(&builder.write().unwrap()).unwrap();
+    assert!(filtered.entry_byname("a.txt").is_none());
+    assert!(filtered.entry_byname("b.txt").is_some());
+}
+
+#[test]
+fn tree_updater() {
+    let (_dir, repo) = temp_repo(true);
+    let tree = nested_tree(&repo);
+    let blob = repo.blob_create_frombuffer("new\n".as_bytes()).unwrap();
+    let dir_id = *tree.entry_byname("dir").unwrap().id();
+
+    let mut updater = TreeUpdater::new(&repo, Some(&*tree));
+    updater.upsert_blob("new/deep/e.txt", blob.id(), git2::GIT_FILEMODE_BLOB_EXECUTABLE);
+    updater.upsert_blob("a.txt", blob.id(), git2::GIT_FILEMODE_BLOB);
+    let updated = repo.lookup_tree(&updater.write().unwrap()).unwrap();
+    let paths = tree_paths(updated);
+    assert_eq!(paths, ~[~"a.txt", ~"dir/b.txt", ~"dir/sub/c.txt", ~"new/deep/e.txt"]);
+    assert_eq!(updated.entry_bypath("a.txt").unwrap().id(), blob.id());
+    let e = updated.entry_bypath("new/deep/e.txt").unwrap();
+    assert!(e.filemode() as int == git2::GIT_FILEMODE_BLOB_EXECUTABLE as int);
+    // untouched subtrees are reused
+    assert_eq!(updated.entry_byname("dir").unwrap().id(), &dir_id);
+
+    // removing the only file of a directory removes the directory
+    let mut updater = TreeUpdater::new(&repo, Some(&*updated));
+    updater.delete("dir/sub/c.txt");
+    updater.insert_tree("copy", &dir_id);
+    let updated = repo.lookup_tree(&updater.write().unwrap()).unwrap();
+    let paths = tree_paths(updated);
+    assert_eq!(paths, ~[~"a.txt", ~"copy/b.txt", ~"copy/sub/c.txt", ~"dir/b.txt",
+                        ~"new/deep/e.txt"]);
+
+    // upserting into a directory keeps its other entries
+    let mut updater = TreeUpdater::new(&repo, Some(&*updated));
+    updater.upsert_blob("dir/sub/d.txt", blob.id(), git2::GIT_FILEMODE_BLOB);
+    updater.upsert_blob("copy/b.txt", blob.id(), git2::GIT_FILEMODE_BLOB);
+    let upserted = repo.lookup_tree(&updater.write().unwrap()).unwrap();
+    let paths = tree_paths(upserted);
+    assert_eq!(paths, ~[~"a.txt", ~"copy/b.txt", ~"copy/sub/c.txt", ~"dir/b.txt",
+                        ~"dir/sub/d.txt", ~"new/deep/e.txt"]);
+    assert_eq!(upserted.entry_bypath("copy/b.txt").unwrap().id(), blob.id());
+    assert_eq!(upserted.entry_bypath("copy/sub/c.txt").unwrap().id(),
+               updated.entry_bypath("copy/sub/c.txt").unwrap().id());
+
+    // a directory whose tree cannot be looked up is an error, not an empty directory
+    let mut updater = TreeUpdater::new(&repo, Some(&*updated));
+    updater.insert_tree("bogus", blob.id());
+    updater.upsert_blob("bogus/f.txt", blob.id(), git2::GIT_FILEMODE_BLOB);
+    assert!(updater.write().is_err());
+
+    let mut updater = TreeUpdater::new(&repo, Some(&*updated));
+    updater.delete("dir/missing.txt");
+    assert!(updater.write().is_err());
+    let mut updater = TreeUpdater::new(&repo, None);
+    updater.upsert_blob("a//b.txt", blob.id(), git2::GIT_FILEMODE_BLOB);
+    assert!(updater.write().is_err());
+}
+
+#[test]
+fn blob_streams() {
+    let (dir, repo) = temp_repo(false);
+    let text = "line\n".repeat(5000);
+    let expected = repo.blob_create_frombuffer(text.as_bytes()).unwrap();
+
+    let mut rest = text.as_bytes();
+    let blob = repo.blob_create_fromchunks(None, |buf| {
+        let n = std::cmp::min(buf.len(), std::cmp::min(rest.len(), 1000));
+        std::vec::bytes::copy_memory(buf, rest.slice_to(n));
+        rest = rest.slice_from(n);
+        Ok(n)
+    }).unwrap();
+    assert_eq!(blob.id(), expected.id());
+
+    // an error from the callback is returned, and what was read is not stored
+    let partial: git2::OID = from_str("b648ac92333301658c4f49b315f21130c689c819").unwrap();
+    let mut chunks = ~[Ok("partial\n"), Err((~"read failed", git2::GITERR_OS))];
+    let res = repo.blob_create_fromchunks(None, |buf| {
+        match chunks.shift() {
+            Ok(chunk) => {
+                std::vec::bytes::copy_memory(buf, chunk.as_bytes());
+                Ok(chunk.len())
+            },
+            Err(e) => Err(e),
+        }
+    });
+    match res {
+        Err((msg, klass)) => {
+            assert_eq!(msg, ~"read failed");
+            assert_eq!(klass, git2::GITERR_OS);
+        },
+        Ok(_) => fail!("stored a blob from a failing callback"),
+    }
+    assert!(!repo.odb().unwrap().exists(&partial));
+
+    let mut reader = MemReader::new(text.as_bytes().to_owned());
+    let blob = repo.blob_create_fromreader(None, &mut reader).unwrap();
+    assert_eq!(blob.id(), expected.id());
+    // reading a directory fails
+    let mut reader = File::open(dir.path()).unwrap();
+    match repo.blob_create_fromreader(None, &mut reader) {
+        Err((_, klass)) => assert_eq!(klass, git2::GITERR_OS),
+        Ok(_) => fail!("stored a blob from a failing reader"),
+    }
+
+    let mut writer = repo.blob_writer(None).unwrap();
+    writer.write(text.slice_to(7).as_bytes());
+    writer.write(text.slice_from(7).as_bytes());
+    assert_eq!(&writer.commit().unwrap(), expected.id());
+
+    // a writer dropped before commit stores nothing
+    {
+        let mut writer = repo.blob_writer(None).unwrap();
+        writer.write("never stored\n".as_bytes());
+    }
+    let never: git2::OID = from_str("016e7551be0ff55f1179147e40cce15714214310").unwrap();
+    assert!(!repo.odb().unwrap().exists(&never));
+
+    // filters apply when a path is given
+    let config = repo.config().unwrap().open_level(GIT_CONFIG_LEVEL_LOCAL).unwrap();
+    config.set_bool("core.autocrlf", true).unwrap();
+    let mut reader = MemReader::new("a\r\nb\r\n".as_bytes().to_owned());
+    let blob = repo.blob_create_fromreader(Some("a.txt"), &mut reader).unwrap();
+    blob.rawcontent_as_slice(|content| assert_eq!(content, "a\nb\n".as_bytes()));
+    let mut writer = repo.blob_writer(Some("a.txt")).unwrap();
+    writer.write("a\r\nb\r\n".as_bytes());
+    assert_eq!(&writer.commit().unwrap(), blob.id());
+}
+
+#[test]
+fn filters() {
+    let (dir, repo) = temp_repo(false);
+    write_file(dir.path(), ".gitattributes", "*.txt text eol=crlf\n*.c ident\n");
+    write_file(dir.path(), "crlf.txt", "a\r\nb\r\n");
+
+    let blob = repo.blob_create_frombuffer("a\nb\n".as_bytes()).unwrap();
+    assert_eq!(blob.filtered_content("x.txt").unwrap(), "a\r\nb\r\n".as_bytes().to_owned());
+    assert_eq!(blob.filtered_content("x.bin").unwrap(), "a\nb\n".as_bytes().to_owned());
+
+    let clean = FilterList::load(&repo, None, "x.txt", GIT_FILTER_TO_ODB).unwrap();
+    assert!(clean.contains("crlf"));
+    assert!(!clean.contains("ident"));
+    assert_eq!(clean.apply_to_data("a\r\nb\r\n".as_bytes()).unwrap(),
+               "a\nb\n".as_bytes().to_owned());
+    assert_eq!(clean.apply_to_file("crlf.txt").unwrap(), "a\nb\n".as_bytes().to_owned());
+
+    let smudge = FilterList::load(&repo, None, "x.txt", GIT_FILTER_TO_WORKTREE).unwrap();
+    assert_eq!(smudge.apply_to_blob(&*blob).unwrap(), "a\r\nb\r\n".as_bytes().to_owned());
+
+    let ident = repo.blob_create_frombuffer("$Id$\n".as_bytes()).unwrap();
+    let smudge = FilterList::load(&repo, Some(&*ident), "main.c", GIT_FILTER_TO_WORKTREE).unwrap();
+    let expanded = format!("$Id: {} $\n", ident.id().to_str());
+    assert_eq!(smudge.apply_to_blob(&*ident).unwrap(), expanded.as_bytes().to_owned());
+    let clean = FilterList::load(&repo, None, "main.c", GIT_FILTER_TO_ODB).unwrap();
+    assert_eq!(clean.apply_to_data(expanded.as_bytes()).unwrap(), "$Id$\n".as_bytes().to_owned());
+
+    let none = FilterList::load(&repo, None, "x.bin", GIT_FILTER_TO_ODB).unwrap();
+    assert_eq!(none.len(), 0);
+    assert_eq!(none.apply_to_data("a\r\n".as_bytes()).unwrap(), "a\r\n".as_bytes().to_owned());
+}
+
+#[deriving(Eq)]
+enum FilterEvent {
+    Checked(~str, ~[Option<~str>]),
+    // path, and whether the content goes to the ODB
+    Applied(~str, bool),
+    CleanedUp(~str),
+}
+
+// stores text upper-cased, and checks it out lower-cased. The calls are
+// reported on `events` and checked by the test: failing inside them would
+// unwind through libgit2.
+struct CaseFilter {
+    events: Chan<FilterEvent>,
+}
+
+impl Filter for CaseFilter {
+    fn check(&mut self, src: &FilterSource, attr_values: &[Option<~str>]) -> bool {
+        self.events.send(Checked(src.path.clone(), attr_values.to_owned()));
+        !src.path.ends_with(".keep")
+    }
+
+    fn apply(&mut self, src: &FilterSource, input: &[u8])
+        -> Result<Option<~[u8]>, (~str, git2::GitError)> {
+        let (to_odb, from, to) = match src.mode {
+            GIT_FILTER_TO_ODB => (true, 'a' as u8, 'A' as u8),
+            GIT_FILTER_TO_WORKTREE => (false, 'A' as u8, 'a' as u8),
+        };
+        self.events.send(Applied(src.path.clone(), to_odb));
+        Ok( Some( input.iter().map(|&b| {
+            if b >= from && b < from + 26 { b - from + to } else { b }
+        }).collect() ) )
+    }
+
+    fn cleanup(&mut self, src: &FilterSource) {
+        self.events.send(CleanedUp(src.path.clone()));
+    }
+}
+
+// the filter calls made since the last time
+fn filter_events(port: &Port<FilterEvent>) -> ~[FilterEvent] {
+    let mut events = ~[];
+    loop {
+        match port.try_recv() {
+            Some(event) => events.push(event),
+            None => return events,
+        }
+    }
+}
+
+#[test]
+fn custom_filter() {
+    let (dir, repo) = temp_repo(false);
+    write_file(dir.path(), ".gitattributes", "*.case filter=case\n*.keep filter=case\n");
+    write_file(dir.path(), "a.case", "Hello\n");
+    write_file(dir.path(), "b.keep", "Hello\n");
+    let (port, chan) = Chan::new();
+    filter_register("case", "filter=case", GIT_FILTER_DRIVER_PRIORITY,
+                    ~CaseFilter { events: chan } as ~Filter).unwrap();
+    let (_, other) = Chan::new();
+    assert!(filter_register("case", "filter=case", GIT_FILTER_DRIVER_PRIORITY,
+                            ~CaseFilter { events: other } as ~Filter).is_err());
+
+    let blob = repo.blob_create_fromworkdir("a.case").unwrap();
+    blob.rawcontent_as_slice(|content| assert_eq!(content, "HELLO\n".as_bytes()));
+    let events = filter_events(&port);
+    assert!(events.contains(&Checked(~"a.case", ~[Some(~"case")])));
+    assert!(events.contains(&Applied(~"a.case", true)));
+    assert!(events.contains(&CleanedUp(~"a.case")));
+    assert_eq!(blob.filtered_content("a.case").unwrap(), "hello\n".as_bytes().to_owned());
+    assert!(filter_events(&port).contains(&Applied(~"a.case", false)));
+
+    // check turns the filter down, so it is neither applied nor cleaned up
+    let blob = repo.blob_create_fromworkdir("b.keep").unwrap();
+    blob.rawcontent_as_slice(|content| assert_eq!(content, "Hello\n".as_bytes()));
+    let events = filter_events(&port);
+    assert_eq!(events, ~[Checked(~"b.keep", ~[Some(~"case")])]);
+
+    {
+        let filters = FilterList::load(&repo, None, "a.case", GIT_FILTER_TO_ODB).unwrap();
+        assert!(filters.contains("case"));
+        // files without the attribute are left alone
+        let filters = FilterList::load(&repo, None, "a.txt", GIT_FILTER_TO_ODB).unwrap();
+        assert!(!filters.contains("case"));
+    }
+    assert_eq!(filter_events(&port), ~[Checked(~"a.case", ~[Some(~"case")]),
+                                       CleanedUp(~"a.case")]);
+
+    // checkout smudges
+    fs::unlink(&dir.path().join("a.case"));
+    checkout_blob(&repo, "a.case", "HELLO\n");
+    assert_eq!(read_file(dir.path(), "a.case"), ~"hello\n");
+    let events = filter_events(&port);
+    assert!(events.contains(&Applied(~"a.case", false)));
+    assert!(events.contains(&CleanedUp(~"a.case")));
+
+    // comparing the working directory to the index cleans
+    write_file(dir.path(), "a.case", "bye\n");
+    let index = repo.index().unwrap();
+    let mut updated: ~[~str] = ~[];
+    index.update_all([], |path, _| {
+        updated.push(path.to_owned());
+        git2::WalkPass
+    }).unwrap();
+    assert_eq!(updated, ~[~"a.case"]);
+    assert!(filter_events(&port).contains(&Applied(~"a.case", true)));
+    let bye = repo.blob_create_frombuffer("BYE\n".as_bytes()).unwrap();
+    assert_eq!(index.write_tree().unwrap().entry_byname("a.case").unwrap().id(), bye.id());
+
+    // so does diffing them: with the same size but another mtime, the file is
+    // hashed, and it cleans to the indexed content
+    write_file(dir.path(), "a.case", "Bye\n");
+    fs::change_file_times(&dir.path().join("a.case"), 1000000, 1000000);
+    assert_eq!(index.diff_to_workdir().unwrap().num_deltas(), 0);
+    assert!(filter_events(&port).contains(&Applied(~"a.case", true)));
+
+    filter_unregister("case").unwrap();
+    let diff = index.diff_to_workdir().unwrap();
+    assert_eq!(diff.num_deltas(), 1);
+    let (status, old_file, new_file) = diff.delta(0).unwrap();
+    assert!(status as int == git2::GIT_DELTA_MODIFIED as int);
+    assert_eq!(old_file.oid, *bye.id());
+    assert_eq!(new_file.path, ~"a.case");
+    assert!(diff.delta(1).is_none());
+    let blob = repo.blob_create_fromworkdir("a.case").unwrap();
+    blob.rawcontent_as_slice(|content| assert_eq!(content, "Bye\n".as_bytes()));
+}
+
+#[test]
+fn odb() {
+    let (_dir, repo) = temp_repo(true);
+    let odb = repo.odb().unwrap();
+    let blob = repo.blob_create_frombuffer("hello\n".as_bytes()).unwrap();
+
+    let id = odb.write(git2::GIT_OBJ_BLOB, "hello\n".as_bytes()).unwrap();
+    assert_eq!(&id, blob.id());
+    assert!(odb.exists(&id));
+    let (otype, size) = odb.read_header(&id).unwrap();
+    assert!(otype as int == git2::GIT_OBJ_BLOB as int);
+    assert_eq!(size, 6);
+    let (otype, data) = odb.read(&id).unwrap();
+    assert!(otype as int == git2::GIT_OBJ_BLOB as int);
+    assert_eq!(data, "hello\n".as_bytes().to_owned());
+
+    let tree = nested_tree(&repo);
+    let (otype, _) = odb.read_header(tree.id()).unwrap();
+    assert!(otype as int == git2::GIT_OBJ_TREE as int);
+
+    let missing = git2::OID { id: [0xab, ..20] };
+    assert!(!odb.exists(&missing));
+    assert!(odb.read_header(&missing).is_err());
+    assert!(odb.read(&missing).is_err());
+
+    let mut writer = odb.open_wstream(13, git2::GIT_OBJ_BLOB).unwrap();
+    writer.write("streamed".as_bytes());
+    writer.write(" blob".as_bytes());
+    let streamed = writer.finalize().unwrap();
+    let (_, data) = odb.read(&streamed).unwrap();
+    assert_eq!(data, "streamed blob".as_bytes().to_owned());
+
+    let mut ids = ~[];
+    odb.foreach(|id| { ids.push(*id); true }).unwrap();
+    assert!(ids.contains(&id));
+    assert!(ids.contains(&streamed));
+    assert!(ids.contains(tree.id()));
+    let mut count = 0;
+    odb.foreach(|_| { count += 1; false }).unwrap();
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn odb_rstream() {
+    let (src_dir, src) = temp_repo(true);
+    let text = "line\n".repeat(5000);
+    commit_blob(&src, Some("refs/heads/master"), "a.txt", text);
+    let id = *src.blob_create_frombuffer(text.as_bytes()).unwrap().id();
+
+    // loose objects are streamed
+    let odb = src.odb().unwrap();
+    let mut reader = odb.open_rstream(&id).unwrap();
+    assert_eq!(reader.read_to_end(), text.as_bytes().to_owned());
+    assert!(odb.open_rstream(&git2::OID { id: [0xab, ..20] }).is_err());
+
+    // fetched objects are packed, and the pack backend cannot stream
+    let (dir, repo) = temp_repo(true);
+    let url = format!("file://{}", src_dir.path().as_str().unwrap());
+    let origin = repo.remote_create("origin", url).unwrap();
+    origin.fetch([], &FetchOptions::new(), &mut NoCallbacks, None).unwrap();
+    let hex = id.to_str();
+    assert!(!dir.path().join_many(["objects", hex.slice_to(2), hex.slice_from(2)]).exists());
+    let odb = repo.odb().unwrap();
+    let mut reader = odb.open_rstream(&id).unwrap();
+    let mut buf = std::vec::from_elem(1000, 0u8);
+    assert_eq!(reader.read(buf), Some(1000));
+    assert_eq!(buf.slice_to(5), "line\n".as_bytes());
+    assert_eq!(reader.read_to_end(), text.slice_from(1000).as_bytes().to_owned());
+    assert_eq!(reader.read(buf), None);
+}
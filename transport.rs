@@ -0,0 +1,596 @@
+use std::{ptr, vec, str, cast, c_str};
+use std::libc::{c_char, c_int, c_uint, c_void, size_t};
+use std::unstable::mutex::{Mutex, MUTEX_INIT};
+use ext;
+use super::{OID, GitError, GITERR_NET, last_error, set_error};
+use super::Repository;
+use super::remote::{Remote, RemoteHead, TransferProgress, Direction, GIT_DIRECTION_FETCH,
+                    GIT_DIRECTION_PUSH, from_c_remote_head, borrowed_remote};
+
+/// A way of talking to remote repositories, implemented in Rust.
+///
+/// Register a `TransportFactory` for a URL scheme with `transport_register`;
+/// remotes whose URL uses that scheme are then fetched through the transports
+/// it builds.
+///
+/// Pushing is not supported: `Remote::push` fails with a `GITERR_NET` error
+/// on such a remote. A transport that needs to push can be written as a
+/// `SmartSubtransport` instead, which talks the git protocol and lets
+/// libgit2 build and send the pack.
+pub trait Transport {
+    /// Open a connection to the repository at `url`
+    fn connect(&mut self, url: &str, direction: Direction) -> Result<(), (~str, GitError)>;
+
+    /// The references the remote advertises; `local_oid` is ignored.
+    /// Called once per connection.
+    fn ls(&mut self) -> Result<~[RemoteHead], (~str, GitError)>;
+
+    /// Receive the advertised references whose objects are missing from `repo`
+    fn negotiate_fetch(&mut self, _repo: &Repository, _wants: &[RemoteHead])
+        -> Result<(), (~str, GitError)> {
+        Ok(())
+    }
+
+    /// Store the objects the wanted references need into `repo`, and record
+    /// the amount of work done in `stats`
+    fn download_pack(&mut self, repo: &Repository, stats: &mut TransferProgress)
+        -> Result<(), (~str, GitError)>;
+
+    fn is_connected(&self) -> bool;
+
+    /// Stop the current operation as soon as possible
+    fn cancel(&mut self) {}
+
+    /// Close the connection
+    fn close(&mut self) -> Result<(), (~str, GitError)> {
+        Ok(())
+    }
+}
+
+/// Builds a `Transport` for each remote operation on a registered scheme
+pub trait TransportFactory {
+    /// A new transport for fetching from `remote`
+    fn new_transport(&mut self, remote: &Remote) -> Result<~Transport, (~str, GitError)>;
+}
+
+// the advertised heads, kept alive for as long as libgit2 may look at them
+struct Heads {
+    heads: ~[ext::git_remote_head],
+    ptrs: ~[*ext::git_remote_head],
+    strs: ~[c_str::CString],
+}
+
+// libgit2 hands back the vtable pointer, so it must come first
+struct RawTransport {
+    parent: ext::git_transport,
+    transport: ~Transport,
+    heads: Option<Heads>,
+}
+
+fn to_c_heads(heads: ~[RemoteHead]) -> Heads {
+    let mut strs = ~[];
+    let mut c_heads = ~[];
+    for head in heads.iter() {
+        let name = head.name.to_c_str();
+        let c_name = name.with_ref(|p| p);
+        strs.push(name);
+        let c_symref = match head.symref_target {
+            Some(ref target) => {
+                let target = target.to_c_str();
+                let c_target = target.with_ref(|p| p);
+                strs.push(target);
+                c_target
+            },
+            None => ptr::null(),
+        };
+        c_heads.push(ext::git_remote_head {
+            local: 0,
+            oid: head.oid,
+            loid: OID { id: [0, .. 20] },
+            name: c_name,
+            symref_target: c_symref,
+        });
+    }
+    let ptrs = c_heads.iter().map(|h| h as *ext::git_remote_head).collect();
+    Heads { heads: c_heads, ptrs: ptrs, strs: strs }
+}
+
+unsafe fn to_raw<'a>(transport: *ext::git_transport) -> &'a mut RawTransport {
+    cast::transmute(transport)
+}
+
+// the repository stays owned by libgit2, even if f fails
+fn with_repo<T>(repo: *ext::git_repository, f: |&Repository| -> T) -> T {
+    f(&Repository::borrowed(repo))
+}
+
+extern "C" fn set_callbacks_cb(_transport: *ext::git_transport, _progress: ext::callback_t,
+                               _error: ext::callback_t, _certificate_check: ext::callback_t,
+                               _payload: *c_void) -> c_int
+{
+    0
+}
+
+extern "C" fn set_custom_headers_cb(_transport: *ext::git_transport,
+                                    _headers: *ext::git_strarray) -> c_int
+{
+    0
+}
+
+extern "C" fn connect_cb(transport: *ext::git_transport, url: *c_char,
+                         _cred_acquire_cb: ext::callback_t, _cred_acquire_payload: *c_void,
+                         _proxy_opts: *ext::git_proxy_options, direction: c_int,
+                         _flags: c_int) -> c_int
+{
+    unsafe {
+        let raw = to_raw(transport);
+        let url = str::raw::from_c_str(url);
+        let direction = if direction == 0 { GIT_DIRECTION_FETCH } else { GIT_DIRECTION_PUSH };
+        raw.heads = None;
+        match raw.transport.connect(url, direction) {
+            Ok(()) => 0,
+            Err(e) => set_error(e),
+        }
+    }
+}
+
+extern "C" fn ls_cb(out: &mut **ext::git_remote_head, size: &mut size_t,
+                    transport: *ext::git_transport) -> c_int
+{
+    unsafe {
+        let raw = to_raw(transport);
+        if raw.heads.is_none() {
+            match raw.transport.ls() {
+                Ok(heads) => raw.heads = Some( to_c_heads(heads) ),
+                Err(e) => return set_error(e),
+            }
+        }
+        let heads = raw.heads.get_ref();
+        *out = heads.ptrs.as_ptr();
+        *size = heads.ptrs.len() as size_t;
+        0
+    }
+}
+
+extern "C" fn push_cb(_transport: *ext::git_transport, _push: *c_void,
+                      _callbacks: *ext::git_remote_callbacks) -> c_int
+{
+    unsafe {
+        set_error( (~"pushing is not supported by transports implementing Transport; \
+                      use a SmartSubtransport", GITERR_NET) )
+    }
+}
+
+extern "C" fn negotiate_fetch_cb(transport: *ext::git_transport, repo: *ext::git_repository,
+                                 refs: **ext::git_remote_head, count: size_t) -> c_int
+{
+    unsafe {
+        let raw = to_raw(transport);
+        let wants: ~[RemoteHead] = vec::raw::buf_as_slice(refs, count as uint, |heads| {
+            heads.iter().filter(|&&h| (*h).local == 0).map(|&h| from_c_remote_head(h)).collect()
+        });
+        match with_repo(repo, |repo| raw.transport.negotiate_fetch(repo, wants)) {
+            Ok(()) => 0,
+            Err(e) => set_error(e),
+        }
+    }
+}
+
+extern "C" fn download_pack_cb(transport: *ext::git_transport, repo: *ext::git_repository,
+                               stats: &mut ext::git_indexer_progress,
+                               _progress_cb: ext::callback_t, _progress_payload: *c_void) -> c_int
+{
+    unsafe {
+        let raw = to_raw(transport);
+        let mut progress = TransferProgress {
+            total_objects: 0,
+            indexed_objects: 0,
+            received_objects: 0,
+            local_objects: 0,
+            total_deltas: 0,
+            indexed_deltas: 0,
+            received_bytes: 0,
+        };
+        let res = with_repo(repo, |repo| raw.transport.download_pack(repo, &mut progress));
+        stats.total_objects = progress.total_objects as c_uint;
+        stats.indexed_objects = progress.indexed_objects as c_uint;
+        stats.received_objects = progress.received_objects as c_uint;
+        stats.local_objects = progress.local_objects as c_uint;
+        stats.total_deltas = progress.total_deltas as c_uint;
+        stats.indexed_deltas = progress.indexed_deltas as c_uint;
+        stats.received_bytes = progress.received_bytes as size_t;
+        match res {
+            Ok(()) => 0,
+            Err(e) => set_error(e),
+        }
+    }
+}
+
+extern "C" fn is_connected_cb(transport: *ext::git_transport) -> c_int
+{
+    unsafe {
+        to_raw(transport).transport.is_connected() as c_int
+    }
+}
+
+extern "C" fn read_flags_cb(_transport: *ext::git_transport, flags: &mut c_int) -> c_int
+{
+    *flags = 0;
+    0
+}
+
+extern "C" fn cancel_cb(transport: *ext::git_transport)
+{
+    unsafe {
+        to_raw(transport).transport.cancel();
+    }
+}
+
+extern "C" fn close_cb(transport: *ext::git_transport) -> c_int
+{
+    unsafe {
+        match to_raw(transport).transport.close() {
+            Ok(()) => 0,
+            Err(e) => set_error(e),
+        }
+    }
+}
+
+extern "C" fn free_cb(transport: *ext::git_transport)
+{
+    unsafe {
+        let _raw: ~RawTransport = cast::transmute(transport);
+    }
+}
+
+// the remote stays owned by libgit2, and so does its repository
+fn with_remote<T>(remote: *ext::git_remote, f: |&Remote| -> T) -> T {
+    unsafe {
+        let repo = Repository::borrowed(ext::git_remote_owner(remote));
+        f(&borrowed_remote(remote, &repo))
+    }
+}
+
+extern "C" fn transport_factory_cb(out: &mut *ext::git_transport, owner: *ext::git_remote,
+                                   param: *c_void) -> c_int
+{
+    unsafe {
+        let factory: &mut ~TransportFactory = cast::transmute(param);
+        let transport = match with_remote(owner, |remote| factory.new_transport(remote)) {
+            Ok(transport) => transport,
+            Err(e) => return set_error(e),
+        };
+        let raw = ~RawTransport {
+            parent: ext::git_transport {
+                version: ext::GIT_TRANSPORT_VERSION,
+                set_callbacks: cast::transmute(set_callbacks_cb),
+                set_custom_headers: cast::transmute(set_custom_headers_cb),
+                connect: cast::transmute(connect_cb),
+                ls: cast::transmute(ls_cb),
+                push: cast::transmute(push_cb),
+                negotiate_fetch: cast::transmute(negotiate_fetch_cb),
+                download_pack: cast::transmute(download_pack_cb),
+                is_connected: cast::transmute(is_connected_cb),
+                read_flags: cast::transmute(read_flags_cb),
+                cancel: cast::transmute(cancel_cb),
+                close: cast::transmute(close_cb),
+                free: cast::transmute(free_cb),
+            },
+            transport: transport,
+            heads: None,
+        };
+        *out = cast::transmute(raw);
+        0
+    }
+}
+
+// libgit2 keeps only a pointer to the factory of a scheme, so the factories
+// are kept here and freed when their scheme is unregistered
+struct Registration {
+    scheme: ~str,
+    param: *c_void,
+    free: fn(*c_void),
+}
+
+static mut REGISTRY_LOCK: Mutex = MUTEX_INIT;
+static mut REGISTRY: *mut ~[Registration] = 0 as *mut ~[Registration];
+
+fn with_registry<T>(f: |&mut ~[Registration]| -> T) -> T {
+    unsafe {
+        REGISTRY_LOCK.lock();
+        if REGISTRY.is_null() {
+            let registry: ~~[Registration] = ~~[];
+            REGISTRY = cast::transmute(registry);
+        }
+        let res = f(&mut *REGISTRY);
+        REGISTRY_LOCK.unlock();
+        res
+    }
+}
+
+// register the transport callback `cb` for `scheme`, freeing `param` with `free`
+// once the scheme is unregistered, or right away if the registration fails
+fn register(scheme: &str, cb: ext::callback_t, param: *c_void, free: fn(*c_void))
+    -> Result<(), (~str, GitError)>
+{
+    super::global_init();
+    with_registry(|registry| {
+        scheme.with_c_str(|c_scheme| {
+            unsafe {
+                if ext::git_transport_register(c_scheme, cb, param) == 0 {
+                    registry.push(Registration { scheme: scheme.to_owned(), param: param,
+                                                 free: free });
+                    Ok(())
+                } else {
+                    let err = last_error();
+                    free(param);
+                    Err(err)
+                }
+            }
+        })
+    })
+}
+
+fn free_transport_factory(param: *c_void) {
+    unsafe {
+        let _factory: ~~TransportFactory = cast::transmute(param);
+    }
+}
+
+/// Use transports built by `factory` for the URLs with the given scheme,
+/// e.g. "ourstore" for `ourstore://` URLs.
+///
+/// Registrations are global to the process. It is an error to register
+/// a scheme twice; unregister it first. The factory is dropped when the
+/// scheme is unregistered, or right away if the registration fails.
+pub fn transport_register(scheme: &str, factory: ~TransportFactory)
+    -> Result<(), (~str, GitError)>
+{
+    unsafe {
+        let boxed: ~~TransportFactory = ~factory;
+        register(scheme, cast::transmute(transport_factory_cb), cast::transmute(boxed),
+                 free_transport_factory)
+    }
+}
+
+/// Remove the transport registered for a scheme with `transport_register`
+/// or `smart_subtransport_register`, and drop its factory
+pub fn transport_unregister(scheme: &str) -> Result<(), (~str, GitError)>
+{
+    super::global_init();
+    with_registry(|registry| {
+        scheme.with_c_str(|c_scheme| {
+            unsafe {
+                if ext::git_transport_unregister(c_scheme) == 0 {
+                    match registry.iter().position(|r| r.scheme.as_slice() == scheme) {
+                        Some(i) => {
+                            let registration = registry.swap_remove(i);
+                            (registration.free)(registration.param);
+                        },
+                        None => (),
+                    }
+                    Ok(())
+                } else {
+                    Err( last_error() )
+                }
+            }
+        })
+    })
+}
+
+/// The requests a smart subtransport carries
+pub enum SmartService {
+    /** Advertise the references to fetch from, e.g. `GET /info/refs?service=git-upload-pack` */
+    GIT_SERVICE_UPLOADPACK_LS = 1,
+    /** Negotiate and download a pack, e.g. `POST /git-upload-pack` */
+    GIT_SERVICE_UPLOADPACK = 2,
+    /** Advertise the references to push to, e.g. `GET /info/refs?service=git-receive-pack` */
+    GIT_SERVICE_RECEIVEPACK_LS = 3,
+    /** Upload a pack and update references, e.g. `POST /git-receive-pack` */
+    GIT_SERVICE_RECEIVEPACK = 4,
+}
+
+/// The byte stream of one request made through a smart subtransport
+pub trait SmartSubtransportStream {
+    /// Read the next bytes of the response into `buf`, returning how many were read.
+    /// 0 means the response is complete.
+    fn read(&mut self, buf: &mut [u8]) -> Result<uint, (~str, GitError)>;
+
+    /// Append `data` to the request
+    fn write(&mut self, data: &[u8]) -> Result<(), (~str, GitError)>;
+}
+
+/// A carrier of the git smart protocol, e.g. an HTTP client.
+///
+/// Only moves bytes around: libgit2 writes and parses the pkt-lines and
+/// negotiates with the server. Register a `SmartSubtransportFactory` for
+/// a URL scheme with `smart_subtransport_register`.
+pub trait SmartSubtransport {
+    /// Start a request for `service` on the repository at `url`
+    fn action(&mut self, url: &str, service: SmartService)
+        -> Result<~SmartSubtransportStream, (~str, GitError)>;
+
+    /// Close the connection to the server
+    fn close(&mut self) -> Result<(), (~str, GitError)> {
+        Ok(())
+    }
+}
+
+/// Builds a `SmartSubtransport` for each remote operation on a registered scheme
+pub trait SmartSubtransportFactory {
+    /// A new subtransport for fetching from or pushing to `remote`
+    fn new_subtransport(&mut self, remote: &Remote)
+        -> Result<~SmartSubtransport, (~str, GitError)>;
+}
+
+// libgit2 hands back the vtable pointers, so they must come first
+struct RawSubtransport {
+    parent: ext::git_smart_subtransport,
+    subtransport: ~SmartSubtransport,
+}
+
+struct RawStream {
+    parent: ext::git_smart_subtransport_stream,
+    stream: ~SmartSubtransportStream,
+}
+
+extern "C" fn stream_read_cb(stream: *ext::git_smart_subtransport_stream, buffer: *mut u8,
+                             buf_size: size_t, bytes_read: &mut size_t) -> c_int
+{
+    unsafe {
+        let raw: &mut RawStream = cast::transmute(stream);
+        let res = vec::raw::mut_buf_as_slice(buffer, buf_size as uint, |buf| {
+            raw.stream.read(buf)
+        });
+        match res {
+            Ok(n) => {
+                *bytes_read = n as size_t;
+                0
+            },
+            Err(e) => set_error(e),
+        }
+    }
+}
+
+extern "C" fn stream_write_cb(stream: *ext::git_smart_subtransport_stream, buffer: *u8,
+                              len: size_t) -> c_int
+{
+    unsafe {
+        let raw: &mut RawStream = cast::transmute(stream);
+        let res = vec::raw::buf_as_slice(buffer, len as uint, |data| {
+            raw.stream.write(data)
+        });
+        match res {
+            Ok(()) => 0,
+            Err(e) => set_error(e),
+        }
+    }
+}
+
+extern "C" fn stream_free_cb(stream: *ext::git_smart_subtransport_stream)
+{
+    unsafe {
+        let _raw: ~RawStream = cast::transmute(stream);
+    }
+}
+
+extern "C" fn subtransport_action_cb(out: &mut *ext::git_smart_subtransport_stream,
+                                     subtransport: *ext::git_smart_subtransport,
+                                     url: *c_char, action: ext::git_smart_service_t) -> c_int
+{
+    unsafe {
+        let raw: &mut RawSubtransport = cast::transmute(subtransport);
+        let url = str::raw::from_c_str(url);
+        let service = match action {
+            ext::GIT_SERVICE_UPLOADPACK_LS => GIT_SERVICE_UPLOADPACK_LS,
+            ext::GIT_SERVICE_UPLOADPACK => GIT_SERVICE_UPLOADPACK,
+            ext::GIT_SERVICE_RECEIVEPACK_LS => GIT_SERVICE_RECEIVEPACK_LS,
+            _ => GIT_SERVICE_RECEIVEPACK,
+        };
+        match raw.subtransport.action(url, service) {
+            Ok(stream) => {
+                let raw_stream = ~RawStream {
+                    parent: ext::git_smart_subtransport_stream {
+                        subtransport: subtransport,
+                        read: cast::transmute(stream_read_cb),
+                        write: cast::transmute(stream_write_cb),
+                        free: cast::transmute(stream_free_cb),
+                    },
+                    stream: stream,
+                };
+                *out = cast::transmute(raw_stream);
+                0
+            },
+            Err(e) => set_error(e),
+        }
+    }
+}
+
+extern "C" fn subtransport_close_cb(subtransport: *ext::git_smart_subtransport) -> c_int
+{
+    unsafe {
+        let raw: &mut RawSubtransport = cast::transmute(subtransport);
+        match raw.subtransport.close() {
+            Ok(()) => 0,
+            Err(e) => set_error(e),
+        }
+    }
+}
+
+extern "C" fn subtransport_free_cb(subtransport: *ext::git_smart_subtransport)
+{
+    unsafe {
+        let _raw: ~RawSubtransport = cast::transmute(subtransport);
+    }
+}
+
+// the subtransport built for the transport git_transport_smart is creating
+extern "C" fn subtransport_factory_cb(out: &mut *ext::git_smart_subtransport,
+                                      _owner: *ext::git_transport, param: *c_void) -> c_int
+{
+    unsafe {
+        let pending: &mut Option<~SmartSubtransport> = cast::transmute(param);
+        let raw = ~RawSubtransport {
+            parent: ext::git_smart_subtransport {
+                action: cast::transmute(subtransport_action_cb),
+                close: cast::transmute(subtransport_close_cb),
+                free: cast::transmute(subtransport_free_cb),
+            },
+            subtransport: pending.take_unwrap(),
+        };
+        *out = cast::transmute(raw);
+        0
+    }
+}
+
+struct SmartRegistration {
+    rpc: bool,
+    factory: ~SmartSubtransportFactory,
+}
+
+extern "C" fn smart_transport_cb(out: &mut *ext::git_transport, owner: *ext::git_remote,
+                                 param: *c_void) -> c_int
+{
+    unsafe {
+        let registration: &mut SmartRegistration = cast::transmute(param);
+        let subtransport = match with_remote(owner, |remote| {
+            registration.factory.new_subtransport(remote)
+        }) {
+            Ok(subtransport) => subtransport,
+            Err(e) => return set_error(e),
+        };
+        // git_transport_smart builds the subtransport before it returns,
+        // and does not keep the definition
+        let mut pending = Some(subtransport);
+        let definition = ext::git_smart_subtransport_definition {
+            callback: cast::transmute(subtransport_factory_cb),
+            rpc: registration.rpc as c_uint,
+            param: cast::transmute(&mut pending),
+        };
+        ext::git_transport_smart(out, owner, cast::transmute(&definition))
+    }
+}
+
+fn free_smart_registration(param: *c_void) {
+    unsafe {
+        let _registration: ~SmartRegistration = cast::transmute(param);
+    }
+}
+
+/// Speak the git smart protocol over the subtransports built by `factory`
+/// for the URLs with the given scheme.
+///
+/// rpc: true if every request is independent, as with HTTP. libgit2 then
+///  resends the state of the negotiation with each request. False if the
+///  requests of a fetch or push share one connection, as with SSH.
+///
+/// Remove the registration with `transport_unregister`, which drops the factory.
+pub fn smart_subtransport_register(scheme: &str, rpc: bool, factory: ~SmartSubtransportFactory)
+    -> Result<(), (~str, GitError)>
+{
+    unsafe {
+        let registration = ~SmartRegistration { rpc: rpc, factory: factory };
+        register(scheme, cast::transmute(smart_transport_cb), cast::transmute(registration),
+                 free_smart_registration)
+    }
+}
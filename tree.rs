@@ -1,11 +1,17 @@
-use std::libc::c_int;
-use std::{ptr, cast};
+use std::libc::{c_char, c_int, c_void, size_t};
+use std::{ptr, vec, cast};
 use std::str::raw::from_c_str;
-use super::{OID, OType, FileMode, GitError};
+use super::{OID, OType, GIT_OBJ_TREE, GIT_OBJ_BLOB, GIT_OBJ_COMMIT, FileMode, GitError};
+use super::{GIT_FILEMODE_NEW, GIT_FILEMODE_TREE, GITERR_TREE};
+use super::{WalkMode, WalkStop, Object, ObjCommit, ObjTree, ObjBlob};
 use super::{git_error, last_error};
 use super::Repository;
+use super::blob::Blob;
+use super::commit::Commit;
 use ext;
 
+pub use ext::{git_treewalk_mode, GIT_TREEWALK_PRE, GIT_TREEWALK_POST};
+
 pub struct Tree<'r> {
     // TODO: make this field priv
     tree: *ext::git_tree,
@@ -29,6 +35,36 @@ impl<'r> Tree<'r> {
         }
     }
 
+    /// Get the number of entries listed in a tree
+    pub fn len(&self) -> uint
+    {
+        unsafe {
+            ext::git_tree_entrycount(self.tree) as uint
+        }
+    }
+
+    /// Lookup a tree entry by its position in the tree
+    pub fn entry_byindex(&self, idx: uint) -> Option<~TreeEntry>
+    {
+        unsafe {
+            let entry_ptr = ext::git_tree_entry_byindex(self.tree, idx as size_t);
+            if entry_ptr == ptr::null() {
+                None
+            } else {
+                Some( ~TreeEntry::new(entry_ptr, false) )
+            }
+        }
+    }
+
+    /// Iterate over the entries of the tree, sorted by name
+    pub fn iter<'a>(&'a self) -> TreeEntries<'a, 'r>
+    {
+        TreeEntries {
+            tree: self,
+            idx: 0,
+        }
+    }
+
     /// Lookup a tree entry by its filename
     pub fn entry_byname(&self, filename: &str) -> Option<~TreeEntry>
     {
@@ -49,7 +85,7 @@ impl<'r> Tree<'r> {
     pub fn entry_byoid(&self, oid: &OID) -> Option<~TreeEntry>
     {
         unsafe {
-            let entry_ptr = ext::git_tree_entry_byoid(self.tree, oid);
+            let entry_ptr = ext::git_tree_entry_byid(self.tree, oid);
             if entry_ptr == ptr::null() {
                 None
             } else {
@@ -73,6 +109,149 @@ impl<'r> Tree<'r> {
             }
         })
     }
+
+    /// Traverse the entries in a tree and its subtrees in pre or post order.
+    ///
+    /// The callback gets the path of the directory holding the entry, like
+    /// "" for the root or "dir/sub/", and the entry itself. Returning
+    /// WalkSkip from a pre-order walk skips the subtree of the entry, and
+    /// WalkStop ends the walk, which is not an error.
+    pub fn walk(&self, mode: git_treewalk_mode, callback: |&str, &TreeEntry| -> WalkMode)
+        -> Result<(), (~str, GitError)>
+    {
+        let mut callback = callback;
+        let mut stopped = false;
+        let res = {
+            let mut wrapper: |&str, &TreeEntry| -> WalkMode = |root, entry| {
+                let walk = callback(root, entry);
+                match walk {
+                    WalkStop => stopped = true,
+                    _ => (),
+                }
+                walk
+            };
+            unsafe {
+                let payload: *c_void = cast::transmute(&mut wrapper);
+                ext::git_tree_walk(self.tree, mode, cast::transmute(tree_walk_cb), payload)
+            }
+        };
+        if res == 0 || stopped {
+            Ok(())
+        } else {
+            unsafe {
+                Err( last_error() )
+            }
+        }
+    }
+
+    /// Iterate lazily over the full paths of the files in the tree and its
+    /// subtrees, e.g. "dir/sub/file.txt", in the order of a pre-order walk.
+    ///
+    /// Subtrees are only loaded once the iteration reaches them. If one
+    /// cannot be loaded, or the tree itself cannot be, the error is yielded
+    /// and the iteration ends. Submodules are yielded like files.
+    pub fn paths(&self) -> TreePaths<'r>
+    {
+        unsafe {
+            let mut tree: *ext::git_tree = ptr::null();
+            // a second handle to the same tree, so that the iterator does not borrow self
+            if ext::git_object_dup(&mut tree, self.tree) != 0 {
+                return TreePaths { owner: self.owner, stack: ~[], error: Some( last_error() ) };
+            }
+            TreePaths {
+                owner: self.owner,
+                stack: ~[(~"", ~Tree::new(tree, self.owner), 0)],
+                error: None,
+            }
+        }
+    }
+}
+
+impl<'r> Index<uint, ~TreeEntry> for Tree<'r> {
+    /// Get the entry at position `idx`, failing if it is out of bounds
+    fn index(&self, idx: &uint) -> ~TreeEntry {
+        match self.entry_byindex(*idx) {
+            Some(entry) => entry,
+            None => fail!(format!("tree entry index out of bounds: {} >= {}", *idx, self.len())),
+        }
+    }
+}
+
+/// Iterator over the entries of a tree, see `Tree::iter`
+pub struct TreeEntries<'a, 'r> {
+    priv tree: &'a Tree<'r>,
+    priv idx: uint,
+}
+
+impl<'a, 'r> Iterator<~TreeEntry> for TreeEntries<'a, 'r> {
+    fn next(&mut self) -> Option<~TreeEntry> {
+        let entry = self.tree.entry_byindex(self.idx);
+        if entry.is_some() {
+            self.idx += 1;
+        }
+        entry
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        let remaining = self.tree.len() - self.idx;
+        (remaining, Some(remaining))
+    }
+}
+
+extern "C" fn tree_walk_cb(root: *c_char, entry: *ext::git_tree_entry, payload: *c_void) -> c_int
+{
+    unsafe {
+        let callback: &mut |&str, &TreeEntry| -> WalkMode = cast::transmute(payload);
+        let root = from_c_str(root);
+        let entry = TreeEntry::new(entry, false);
+        (*callback)(root, &entry) as c_int
+    }
+}
+
+/// Iterator over the file paths of a tree, see `Tree::paths`
+pub struct TreePaths<'r> {
+    priv owner: &'r Repository,
+    // the directory prefix, tree and next entry index of each level being visited
+    priv stack: ~[(~str, ~Tree<'r>, uint)],
+    // an error to yield before anything else
+    priv error: Option<(~str, GitError)>,
+}
+
+impl<'r> Iterator<Result<~str, (~str, GitError)>> for TreePaths<'r> {
+    fn next(&mut self) -> Option<Result<~str, (~str, GitError)>> {
+        match self.error.take() {
+            Some(e) => return Some( Err(e) ),
+            None => (),
+        }
+        loop {
+            let (prefix, tree, idx) = match self.stack.pop_opt() {
+                Some(level) => level,
+                None => return None,
+            };
+            unsafe {
+                if idx >= ext::git_tree_entrycount(tree.tree) as uint {
+                    continue;
+                }
+                let entry = ext::git_tree_entry_byindex(tree.tree, idx as size_t);
+                let path = format!("{}{}", prefix, from_c_str(ext::git_tree_entry_name(entry)));
+                let otype = ext::git_tree_entry_type(entry);
+                let id = *ext::git_tree_entry_id(entry);
+                self.stack.push((prefix, tree, idx + 1));
+                match otype {
+                    GIT_OBJ_TREE => {
+                        match self.owner.lookup_tree(&id) {
+                            Some(subtree) => self.stack.push((format!("{}/", path), subtree, 0)),
+                            None => {
+                                self.stack.clear();
+                                return Some( Err( last_error() ) );
+                            },
+                        }
+                    },
+                    _ => return Some( Ok(path) ),
+                }
+            }
+        }
+    }
 }
 
 #[unsafe_destructor]
@@ -126,6 +305,54 @@ impl TreeEntry {
             ext::git_tree_entry_filemode(self.tree_entry)
         }
     }
+
+    /// Load the object the entry points to from `repo`
+    ///
+    /// It is an error if the object does not exist, as for the commit
+    /// of a submodule.
+    pub fn to_object<'r>(&self, repo: &'r Repository) -> Result<Object<'r>, (~str, GitError)>
+    {
+        unsafe {
+            let mut obj: *ext::git_object = ptr::null();
+            if ext::git_tree_entry_to_object(&mut obj, repo.repo, self.tree_entry) != 0 {
+                return Err( last_error() );
+            }
+            match ext::git_object_type(obj) {
+                GIT_OBJ_COMMIT => Ok( ObjCommit(~Commit::new(obj, repo)) ),
+                GIT_OBJ_TREE => Ok( ObjTree(~Tree::new(obj, repo)) ),
+                GIT_OBJ_BLOB => Ok( ObjBlob(~Blob::new(obj, repo)) ),
+                _ => {
+                    ext::git_object_free(obj);
+                    Err( (format!("Unexpected object in tree entry '{}'", self.name()),
+                          GITERR_TREE) )
+                },
+            }
+        }
+    }
+
+    /// Load the tree the entry points to from `repo`
+    ///
+    /// Returns None if the entry is not a tree.
+    pub fn to_tree<'r>(&self, repo: &'r Repository) -> Result<Option<~Tree<'r>>, (~str, GitError)>
+    {
+        match self.to_object(repo) {
+            Ok(ObjTree(tree)) => Ok(Some(tree)),
+            Ok(_) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Load the blob the entry points to from `repo`
+    ///
+    /// Returns None if the entry is not a blob.
+    pub fn to_blob<'r>(&self, repo: &'r Repository) -> Result<Option<~Blob<'r>>, (~str, GitError)>
+    {
+        match self.to_object(repo) {
+            Ok(ObjBlob(blob)) => Ok(Some(blob)),
+            Ok(_) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
 }
 
 #[unsafe_destructor]
@@ -142,7 +369,12 @@ impl Drop for TreeEntry {
 impl Clone for TreeEntry {
     fn clone(&self) -> TreeEntry {
         unsafe {
-            TreeEntry::new(ext::git_tree_entry_dup(self.tree_entry), self.owned)
+            // the copy is not tied to a tree, so it must free itself
+            let mut copy: *ext::git_tree_entry = ptr::null();
+            if ext::git_tree_entry_dup(&mut copy, self.tree_entry) != 0 {
+                fail!(~"tree entry copy failure");
+            }
+            TreeEntry::new(copy, true)
         }
     }
 }
@@ -199,22 +431,28 @@ impl TotalOrd for TreeEntry {
     }
 }
 
-impl TreeBuilder {
+impl<'r> TreeBuilder<'r> {
     /// Clear all the entires in the builder
     pub fn clear(&self)
     {
         unsafe {
-            ext::git_treebuilder_clear(self.bld);
+            if ext::git_treebuilder_clear(self.bld) != 0 {
+                git_error::cond.raise(last_error());
+            }
         }
     }
 
     /// Get an entry from the builder from its filename
-    pub fn get(&self, filename: &str) -> ~TreeEntry
+    pub fn get(&self, filename: &str) -> Option<~TreeEntry>
     {
         filename.with_c_str(|c_filename| {
             unsafe {
                 let entry_ptr = ext::git_treebuilder_get(self.bld, c_filename);
-                ~TreeEntry::new(entry_ptr, false)
+                if entry_ptr == ptr::null() {
+                    None
+                } else {
+                    Some( ~TreeEntry::new(entry_ptr, false) )
+                }
             }
         })
     }
@@ -231,12 +469,22 @@ impl TreeBuilder {
     /// to an existing git object in the object database, nor that the
     /// attributes make sense regarding the type of the pointed at object.
     ///
-    /// filename: Filename of the entry
+    /// filename: Filename of the entry. It must not be empty, ".", ".."
+    ///  or contain a "/".
     /// id: SHA1 OID of the entry
     /// filemode: Folder attributes of the entry. This parameter must not be GIT_FILEMODE_NEW
     pub fn insert(&self, filename: &str, id: &OID, filemode: FileMode) ->
         Result<~TreeEntry, (~str, GitError)>
     {
+        match filemode {
+            GIT_FILEMODE_NEW => return Err( (~"Invalid filemode for a tree entry", GITERR_TREE) ),
+            _ => (),
+        }
+        if filename.is_empty() || filename == "." || filename == ".." ||
+            filename.contains_char('/') || filename.contains_char('\0') {
+            return Err( (format!("Invalid name for a tree entry: '{}'", filename), GITERR_TREE) );
+        }
+
         filename.with_c_str(|c_filename| {
             unsafe {
                 let mut entry_ptr:*ext::git_tree_entry = ptr::null();
@@ -261,21 +509,47 @@ impl TreeBuilder {
         })
     }
 
+    /// Selectively remove entries from the builder
+    ///
+    /// The entries for which `keep` returns false are removed.
+    pub fn filter(&self, keep: |&TreeEntry| -> bool)
+    {
+        let mut keep = keep;
+        unsafe {
+            let payload: *c_void = cast::transmute(&mut keep);
+            if ext::git_treebuilder_filter(self.bld, cast::transmute(treebuilder_filter_cb),
+                                           payload) != 0 {
+                git_error::cond.raise(last_error());
+            }
+        }
+    }
+
+    /// Iterate over copies of the entries in the builder, sorted by name
+    pub fn iter(&self) -> vec::MoveIterator<~TreeEntry>
+    {
+        let mut entries = ~[];
+        self.filter(|entry| {
+            entries.push(~entry.clone());
+            true
+        });
+        entries.sort();
+        entries.move_iter()
+    }
+
     /// Write the contents of the tree builder as a tree object
     ///
-    /// The tree builder will be written to the given `repo`, and its
+    /// The tree builder will be written to its repository, and its
     /// identifying SHA1 hash will be returned
-    ///
-    /// repo: Repository in which to store the object
-    pub fn write(&self, repo: &Repository) -> OID
+    pub fn write(&self) -> Result<OID, (~str, GitError)>
     {
         let mut oid = OID { id: [0, ..20] };
         unsafe {
-            if ext::git_treebuilder_write(&mut oid, repo.repo, self.bld) != 0 {
-                git_error::cond.raise(last_error())
+            if ext::git_treebuilder_write(&mut oid, self.bld) == 0 {
+                Ok(oid)
+            } else {
+                Err( last_error() )
             }
         }
-        return oid;
     }
 
     /// Get the number of entries listed in a treebuilder
@@ -287,37 +561,49 @@ impl TreeBuilder {
     }
 }
 
-pub struct TreeBuilder {
+extern "C" fn treebuilder_filter_cb(entry: *ext::git_tree_entry, payload: *c_void) -> c_int
+{
+    unsafe {
+        let keep: &mut |&TreeEntry| -> bool = cast::transmute(payload);
+        let entry = TreeEntry::new(entry, false);
+        // a non-zero return value removes the entry
+        !(*keep)(&entry) as c_int
+    }
+}
+
+pub struct TreeBuilder<'r> {
     priv bld: *ext::git_treebuilder,
+    priv owner: &'r Repository,
 }
 
-impl TreeBuilder {
-    /// Create a new tree builder.
+impl<'r> TreeBuilder<'r> {
+    /// Create a new tree builder for the trees of `repo`.
     /// The tree builder can be used to create or modify trees in memory and
     /// write them as tree objects to the database.
     /// The tree builder will start with no entries and will have to be filled manually.
-    pub fn new() -> TreeBuilder
+    pub fn new(repo: &'r Repository) -> Result<TreeBuilder<'r>, (~str, GitError)>
     {
         let mut bld:*ext::git_treebuilder = ptr::null();
         unsafe {
-            if ext::git_treebuilder_create(&mut bld, ptr::null()) == 0 {
-                TreeBuilder { bld: bld }
+            if ext::git_treebuilder_new(&mut bld, repo.repo, ptr::null()) == 0 {
+                Ok( TreeBuilder { bld: bld, owner: repo } )
             } else {
-                fail!(~"failed to create treebuilder")
+                Err( last_error() )
             }
         }
     }
 
     /// Create a new tree builder.
-    /// The tree builder will be initialized with the entries of the given tree.
-    pub fn from_tree(tree: &Tree) -> TreeBuilder
+    /// The tree builder will be initialized with the entries of the given tree,
+    /// and write to the repository of the tree.
+    pub fn from_tree(tree: &Tree<'r>) -> Result<TreeBuilder<'r>, (~str, GitError)>
     {
         let mut bld:*ext::git_treebuilder = ptr::null();
         unsafe {
-            if ext::git_treebuilder_create(&mut bld, tree.tree) == 0 {
-                TreeBuilder { bld: bld }
+            if ext::git_treebuilder_new(&mut bld, tree.owner.repo, tree.tree) == 0 {
+                Ok( TreeBuilder { bld: bld, owner: tree.owner } )
             } else {
-                fail!(~"failed to create treebuilder")
+                Err( last_error() )
             }
         }
     }
@@ -325,10 +611,196 @@ impl TreeBuilder {
 
 
 #[unsafe_destructor]
-impl Drop for TreeBuilder {
+impl<'r> Drop for TreeBuilder<'r> {
     fn drop(&mut self) {
         unsafe {
             ext::git_treebuilder_free(self.bld);
         }
     }
 }
+
+/// A change to a tree at a path like "dir/sub/file.txt", see `TreeUpdater`
+pub enum TreeUpdate {
+    /// Insert a blob with the given mode, replacing any entry at the path
+    UpsertBlob(~str, OID, FileMode),
+    /// Insert a tree, replacing any entry at the path
+    InsertTree(~str, OID),
+    /// Remove the entry at the path
+    DeletePath(~str),
+}
+
+impl TreeUpdate {
+    fn path<'a>(&'a self) -> &'a str
+    {
+        match *self {
+            UpsertBlob(ref path, _, _) => path.as_slice(),
+            InsertTree(ref path, _) => path.as_slice(),
+            DeletePath(ref path) => path.as_slice(),
+        }
+    }
+}
+
+/// Builds a new tree from a base tree and changes to paths at any depth,
+/// creating the missing directories along the way.
+///
+/// Only the trees on the paths of the changes are rewritten; the other
+/// subtrees of the base are reused as they are.
+pub struct TreeUpdater<'r> {
+    priv owner: &'r Repository,
+    priv base: Option<OID>,
+    priv updates: ~[TreeUpdate],
+}
+
+impl<'r> TreeUpdater<'r> {
+    /// Start from `base`, or from an empty tree if None
+    pub fn new(repo: &'r Repository, base: Option<&Tree>) -> TreeUpdater<'r>
+    {
+        TreeUpdater {
+            owner: repo,
+            base: base.map(|tree| *tree.id()),
+            updates: ~[],
+        }
+    }
+
+    /// Queue a change; changes are applied in the order they are queued
+    pub fn update(&mut self, update: TreeUpdate)
+    {
+        self.updates.push(update);
+    }
+
+    /// Insert a blob at `path` with the given mode, replacing any entry there
+    pub fn upsert_blob(&mut self, path: &str, id: &OID, filemode: FileMode)
+    {
+        self.update(UpsertBlob(path.to_owned(), *id, filemode));
+    }
+
+    /// Insert the tree `id` at `path`, replacing any entry there
+    pub fn insert_tree(&mut self, path: &str, id: &OID)
+    {
+        self.update(InsertTree(path.to_owned(), *id));
+    }
+
+    /// Remove the entry at `path`. It is an error if there is none.
+    pub fn delete(&mut self, path: &str)
+    {
+        self.update(DeletePath(path.to_owned()));
+    }
+
+    /// Write the changed trees to the repository and return the id of the new root tree
+    ///
+    /// Directories left empty by deletions are removed, except the root.
+    pub fn write(&self) -> Result<OID, (~str, GitError)>
+    {
+        let mut paths = ~[];
+        for update in self.updates.iter() {
+            let components: ~[&str] = update.path().split('/').collect();
+            if components.iter().any(|c| c.is_empty()) {
+                return Err( (format!("Invalid path '{}'", update.path()), GITERR_TREE) );
+            }
+            paths.push(components);
+        }
+        let updates: ~[(&[&str], &TreeUpdate)] =
+            paths.iter().map(|c| c.as_slice()).zip(self.updates.iter()).collect();
+
+        let base = match self.base {
+            Some(ref id) => match self.owner.lookup_tree(id) {
+                Some(tree) => Some(tree),
+                None => unsafe { return Err( last_error() ) },
+            },
+            None => None,
+        };
+        match self.apply(base, updates) {
+            Ok(Some(id)) => Ok(id),
+            Ok(None) => TreeBuilder::new(self.owner).and_then(|empty| empty.write()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // apply the updates, whose paths are relative to `tree`, to a builder
+    // holding the entries of `tree`; returns None if no entries are left
+    fn apply(&self, tree: Option<~Tree<'r>>, updates: &[(&[&str], &TreeUpdate)])
+        -> Result<Option<OID>, (~str, GitError)>
+    {
+        let builder = match tree {
+            Some(ref tree) => TreeBuilder::from_tree(&**tree),
+            None => TreeBuilder::new(self.owner),
+        };
+        let builder = match builder {
+            Ok(b) => b,
+            Err(e) => return Err(e),
+        };
+
+        let mut names: ~[&str] = ~[];
+        for &(components, _) in updates.iter() {
+            if !names.contains(&components[0]) {
+                names.push(components[0]);
+            }
+        }
+
+        for &name in names.iter() {
+            let named: ~[(&[&str], &TreeUpdate)] =
+                updates.iter().filter(|&&(c, _)| c[0] == name).map(|&u| u).collect();
+            let mut state = builder.get(name).map(|e| (*e.id(), e.filemode()));
+            let mut i = 0;
+            while i < named.len() {
+                let (components, update) = named[i];
+                if components.len() == 1 {
+                    state = match *update {
+                        UpsertBlob(_, ref id, filemode) => Some( (*id, filemode) ),
+                        InsertTree(_, ref id) => Some( (*id, GIT_FILEMODE_TREE) ),
+                        DeletePath(ref path) => {
+                            if state.is_none() {
+                                return Err( (format!("Cannot remove '{}': no such entry", *path),
+                                             GITERR_TREE) );
+                            }
+                            None
+                        },
+                    };
+                    i += 1;
+                    continue;
+                }
+
+                // a run of changes below `name` rewrites it as a subtree
+                let mut children = ~[];
+                while i < named.len() {
+                    let (components, update) = named[i];
+                    if components.len() == 1 {
+                        break;
+                    }
+                    children.push((components.slice_from(1), update));
+                    i += 1;
+                }
+                let subtree = match state {
+                    Some((ref id, GIT_FILEMODE_TREE)) => match self.owner.lookup_tree(id) {
+                        Some(subtree) => Some(subtree),
+                        None => unsafe { return Err( last_error() ) },
+                    },
+                    _ => None,
+                };
+                state = match self.apply(subtree, children) {
+                    Ok(Some(id)) => Some( (id, GIT_FILEMODE_TREE) ),
+                    Ok(None) => None,
+                    Err(e) => return Err(e),
+                };
+            }
+
+            match state {
+                Some((ref id, filemode)) => {
+                    match builder.insert(name, id, filemode) {
+                        Ok(_) => (),
+                        Err(e) => return Err(e),
+                    }
+                },
+                None => {
+                    builder.remove(name);
+                },
+            }
+        }
+
+        if builder.entrycount() == 0 {
+            Ok(None)
+        } else {
+            builder.write().map(|id| Some(id))
+        }
+    }
+}